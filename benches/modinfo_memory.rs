@@ -0,0 +1,141 @@
+//! Compare peak memory use of reading [`ModInfo`] from a large module.
+//!
+//! [`ModuleFile::from_name`] and [`ModuleFile::from_path`] only read the parts
+//! of uncompressed modules they need, instead of the whole file, which matters
+//! for modules like `amdgpu.ko` that can be hundreds of MiB.
+//!
+//! Peak memory can only go up within a process, so each case runs in a child
+//! process which reports its own peak resident set size.
+//!
+//! Run with `cargo bench --bench modinfo_memory`.
+use linapi::system::modules::{ModInfo, ModuleFile};
+use std::{
+    env, fs,
+    io::{prelude::*, SeekFrom},
+    mem::MaybeUninit,
+    path::Path,
+    process::{self, Command},
+};
+
+/// Size of the padding section in the generated module.
+const PADDING: u64 = 256 * 1024 * 1024;
+
+/// Write a minimal 64-bit little endian ELF module to `path`, with a
+/// `.modinfo` section and [`PADDING`] bytes of other section data.
+///
+/// The padding is a hole in the file, so this is fast and uses no disk.
+fn write_module(path: &Path) {
+    let modinfo = b"license=GPL\0name=bench\0vermagic=6.1.0 SMP mod_unload\0";
+    let names = b"\0.modinfo\0.padding\0.shstrtab\0";
+    let modinfo_off = 64;
+    let names_off = modinfo_off + modinfo.len() as u64;
+    let padding_off = names_off + names.len() as u64;
+    // Section headers must be aligned
+    let sh_off = (padding_off + PADDING + 7) & !7;
+    //
+    let mut f = fs::File::create(path).unwrap();
+    let mut header = Vec::new();
+    header.extend_from_slice(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0");
+    // e_type ET_REL, e_machine x86_64, e_version
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&62u16.to_le_bytes());
+    header.extend_from_slice(&1u32.to_le_bytes());
+    // e_entry, e_phoff, e_shoff
+    header.extend_from_slice(&0u64.to_le_bytes());
+    header.extend_from_slice(&0u64.to_le_bytes());
+    header.extend_from_slice(&sh_off.to_le_bytes());
+    // e_flags, e_ehsize, e_phentsize, e_phnum, e_shentsize, e_shnum, e_shstrndx
+    header.extend_from_slice(&0u32.to_le_bytes());
+    for v in [64u16, 0, 0, 64, 4, 3] {
+        header.extend_from_slice(&v.to_le_bytes());
+    }
+    f.write_all(&header).unwrap();
+    f.write_all(modinfo).unwrap();
+    f.write_all(names).unwrap();
+    f.set_len(sh_off).unwrap();
+    f.seek(SeekFrom::Start(sh_off)).unwrap();
+    // Null section, then `(sh_name, sh_type, sh_offset, sh_size)`
+    f.write_all(&[0; 64]).unwrap();
+    for (name, type_, offset, size) in [
+        (1u32, 1u32, modinfo_off, modinfo.len() as u64),
+        (10, 1, padding_off, PADDING),
+        (19, 3, names_off, names.len() as u64),
+    ] {
+        let mut sh = Vec::new();
+        sh.extend_from_slice(&name.to_le_bytes());
+        sh.extend_from_slice(&type_.to_le_bytes());
+        // sh_flags, sh_addr
+        sh.extend_from_slice(&[0; 16]);
+        sh.extend_from_slice(&offset.to_le_bytes());
+        sh.extend_from_slice(&size.to_le_bytes());
+        // sh_link, sh_info, sh_addralign, sh_entsize
+        sh.extend_from_slice(&[0; 24]);
+        f.write_all(&sh).unwrap();
+    }
+}
+
+/// Peak resident set size of this process, in KiB.
+fn peak_rss() -> i64 {
+    let mut usage = MaybeUninit::uninit();
+    // Safe because `usage` is a valid pointer, and initialized on success.
+    let usage = unsafe {
+        assert_eq!(libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()), 0);
+        usage.assume_init()
+    };
+    usage.ru_maxrss
+}
+
+/// Run `case` on `path` in a child process, returning its peak memory.
+fn run_child(case: &str, path: &Path) -> i64 {
+    let out = Command::new(env::current_exe().unwrap())
+        .args(["--child", case])
+        .arg(path)
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout)
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Some(i) = args.iter().position(|a| a == "--child") {
+        let path = Path::new(&args[i + 2]);
+        match &*args[i + 1] {
+            "baseline" => {}
+            // What `ModuleFile::from_name` used to do, reading the whole file.
+            "read" => {
+                let info = ModInfo::from_bytes(&fs::read(path).unwrap()).unwrap();
+                assert_eq!(info.name, "bench");
+            }
+            "from_path" => {
+                let m = ModuleFile::from_path(path).unwrap();
+                assert_eq!(m.info().name, "bench");
+            }
+            case => panic!("Unknown case {}", case),
+        }
+        println!("{}", peak_rss());
+        return;
+    }
+    let path = env::temp_dir().join(format!("linapi-bench-{}.ko", process::id()));
+    write_module(&path);
+    println!(
+        "Peak memory reading .modinfo from a {} MiB module",
+        PADDING >> 20
+    );
+    for (case, desc) in [
+        ("baseline", "baseline"),
+        ("read", "fs::read and ModInfo::from_bytes"),
+        ("from_path", "ModuleFile::from_path"),
+    ] {
+        println!("{:>34}: {} KiB", desc, run_child(case, &path));
+    }
+    fs::remove_file(&path).unwrap();
+}
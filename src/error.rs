@@ -15,8 +15,46 @@ pub enum ModuleError {
     /// Couldn't unload module {0}: {1}
     UnloadError(String, String),
 
+    /// Couldn't reload module {0}, it is still loaded: {1}
+    ReloadError(String, String),
+
+    /// Couldn't reload module {0}, it was unloaded but not loaded again: {1}
+    ReloadUnloaded(String, String),
+
     /// Module was invalid: `{0}`
     InvalidModule(String),
+
+    /// Module dependency cycle involving `{0}`
+    DependencyCycle(String),
+
+    /// Module {module} vermagic `{vermagic}` doesn't start with kernel release `{kernel_release}`
+    Incompatible {
+        /// Module name
+        module: String,
+
+        /// Release of the running kernel
+        kernel_release: String,
+
+        /// The modules full vermagic, only the first part of which, the kernel
+        /// release, was compared.
+        vermagic: String,
+    },
+
+    /// Module parameter `{0}` is read-only, or permission was denied
+    ParameterPermission(String),
+
+    /// Module parameter name `{0}` is invalid
+    InvalidParameter(String),
+}
+
+/// Error type for [`crate::system::modules::ModuleFile::validate_params`]
+#[derive(Debug, Display, Error)]
+pub enum ParamError {
+    /// Unknown module parameter `{0}`
+    UnknownName(String),
+
+    /// Invalid value for module parameter `{0}`, expected `{1}`
+    TypeMismatch(String, String),
 }
 
 #[derive(Debug, Display)]
@@ -83,4 +121,12 @@ pub(crate) mod text {
     pub const NAME: &str = "invalid module name";
 
     pub const MODINFO: &str = "invalid .modinfo";
+
+    pub const IN_USE: &str = "module is in use";
+
+    pub const PROC_MODULES: &str = "invalid /proc/modules entry";
+
+    pub const MODULES_DEP: &str = "not found in modules.dep";
+
+    pub const SIGNATURE: &str = "invalid module signature";
 }
@@ -1,7 +1,9 @@
 //! Linux-specific extensions to std types
+use bitflags::bitflags;
 use nix::{
     errno::Errno,
-    fcntl::{fallocate, flock, FallocateFlags, FlockArg},
+    fcntl::{copy_file_range, fallocate, flock, FallocateFlags, FlockArg},
+    libc::c_int,
     sys::memfd::{memfd_create, MemFdCreateFlag},
 };
 use std::{
@@ -20,14 +22,18 @@ use std::{
 mod _impl {
     use nix::{
         ioctl_none,
+        ioctl_read_bad,
+        ioctl_readwrite_bad,
         ioctl_write_ptr_bad,
-        libc::{c_char, c_int, c_longlong, c_void},
+        libc::{c_char, c_int, c_longlong, c_uint, c_void},
+        request_code_read,
+        request_code_readwrite,
     };
     use std::{convert::TryInto, marker::PhantomData, mem};
 
     pub const BLOCK_ADD_PART: i32 = 1;
     pub const BLOCK_DEL_PART: i32 = 2;
-    pub const _BLOCK_RESIZE_PART: i32 = 3;
+    pub const BLOCK_RESIZE_PART: i32 = 3;
 
     #[repr(C)]
     pub struct BlockPageIoctlArgs<'a> {
@@ -90,6 +96,73 @@ mod _impl {
         }
     }
 
+    /// Number of extents requested per `FS_IOC_FIEMAP` call
+    pub const FIEMAP_BATCH: usize = 32;
+
+    /// Sync the file before mapping extents
+    pub const FIEMAP_FLAG_SYNC: u32 = 1;
+
+    /// `struct fiemap_extent` from <linux/fiemap.h>
+    #[repr(C)]
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct FiemapExtent {
+        /// Byte offset of the extent in the file
+        pub logical: u64,
+
+        /// Byte offset of the extent on disk
+        pub physical: u64,
+
+        /// Length in bytes
+        pub length: u64,
+
+        reserved64: [u64; 2],
+
+        /// `FIEMAP_EXTENT_*` flags
+        pub flags: u32,
+
+        reserved: [u32; 3],
+    }
+
+    /// `struct fiemap` from <linux/fiemap.h>
+    ///
+    /// The kernel defines `extents` as a flexible array member, the number of
+    /// entries is given by `extent_count`.
+    #[repr(C)]
+    pub struct Fiemap {
+        /// Byte offset to start mapping from
+        pub start: u64,
+
+        /// Number of bytes to map
+        pub length: u64,
+
+        /// `FIEMAP_FLAG_*` flags
+        pub flags: u32,
+
+        /// Number of extents that were mapped, set by the kernel
+        pub mapped_extents: u32,
+
+        /// Size of `extents`
+        pub extent_count: u32,
+
+        reserved: u32,
+
+        pub extents: [FiemapExtent; FIEMAP_BATCH],
+    }
+
+    impl Fiemap {
+        pub fn new(start: u64) -> Self {
+            Fiemap {
+                start,
+                length: u64::MAX - start,
+                flags: FIEMAP_FLAG_SYNC,
+                mapped_extents: 0,
+                extent_count: FIEMAP_BATCH as u32,
+                reserved: 0,
+                extents: [FiemapExtent::default(); FIEMAP_BATCH],
+            }
+        }
+    }
+
     ioctl_none! {
         /// The `BLKRRPART` ioctl, defined in
         /// <linux/fs.h>
@@ -98,6 +171,14 @@ mod _impl {
         95
     }
 
+    ioctl_none! {
+        /// The `BLKFLSBUF` ioctl, defined in
+        /// <linux/fs.h>
+        block_flush_buffers,
+        0x12,
+        97
+    }
+
     ioctl_write_ptr_bad!(
         /// The `BLKPG` ioctl, defined in
         /// <linux/blkpg.h>
@@ -107,6 +188,83 @@ mod _impl {
         0x1269,
         BlockPageIoctlArgs
     );
+
+    ioctl_read_bad!(
+        /// The `BLKSSZGET` ioctl, defined in
+        /// <linux/fs.h>
+        block_logical_sector_size,
+        0x1268,
+        c_int
+    );
+
+    ioctl_read_bad!(
+        /// The `BLKPBSZGET` ioctl, defined in
+        /// <linux/fs.h>
+        block_physical_block_size,
+        0x127B,
+        c_uint
+    );
+
+    ioctl_read_bad!(
+        /// The `BLKROGET` ioctl, defined in
+        /// <linux/fs.h>
+        block_get_read_only,
+        0x125E,
+        c_int
+    );
+
+    ioctl_write_ptr_bad!(
+        /// The `BLKROSET` ioctl, defined in
+        /// <linux/fs.h>
+        block_set_read_only,
+        0x125D,
+        c_int
+    );
+
+    ioctl_readwrite_bad!(
+        /// The `FS_IOC_FIEMAP` ioctl, defined in
+        /// <linux/fs.h>
+        ///
+        /// Encoded with the size of `struct fiemap` without any extents.
+        file_extent_map,
+        request_code_readwrite!(
+            b'f',
+            11,
+            mem::size_of::<Fiemap>() - mem::size_of::<[FiemapExtent; FIEMAP_BATCH]>()
+        ),
+        Fiemap
+    );
+
+    ioctl_read_bad!(
+        /// The `BLKGETSIZE64` ioctl, defined in
+        /// <linux/fs.h>
+        ///
+        /// Incorrectly defined as `_IOR(0x12, 114, size_t)`, actually returns
+        /// a `u64`
+        block_device_size,
+        request_code_read!(0x12, 114, mem::size_of::<usize>()),
+        u64
+    );
+
+    ioctl_write_ptr_bad!(
+        /// The `BLKDISCARD` ioctl, defined in
+        /// <linux/fs.h>
+        ///
+        /// Takes the byte offset and length to discard.
+        block_discard,
+        0x1277,
+        [u64; 2]
+    );
+
+    ioctl_write_ptr_bad!(
+        /// The `BLKSECDISCARD` ioctl, defined in
+        /// <linux/fs.h>
+        ///
+        /// Takes the byte offset and length to discard.
+        block_secure_discard,
+        0x127D,
+        [u64; 2]
+    );
 }
 
 /// Impl for [`FileExt::lock`] and co.
@@ -132,6 +290,63 @@ fn lock_impl(fd: RawFd, lock: LockType, non_block: bool) -> nix::Result<()> {
     )
 }
 
+bitflags! {
+    /// Flags for an [`Extent`], from <linux/fiemap.h>
+    ///
+    /// Unknown flags are dropped.
+    pub struct ExtentFlags: u32 {
+        /// Last extent in the file
+        const LAST = 0x1;
+
+        /// Data location unknown
+        const UNKNOWN = 0x2;
+
+        /// Location still pending, implies [`ExtentFlags::UNKNOWN`]
+        const DELALLOC = 0x4;
+
+        /// Data can't be read while the filesystem is unmounted
+        const ENCODED = 0x8;
+
+        /// Data is encrypted, implies [`ExtentFlags::ENCODED`]
+        const DATA_ENCRYPTED = 0x80;
+
+        /// Extent offsets may not be block aligned
+        const NOT_ALIGNED = 0x100;
+
+        /// Data is mixed with metadata, implies [`ExtentFlags::NOT_ALIGNED`]
+        const DATA_INLINE = 0x200;
+
+        /// Multiple files in one block, implies [`ExtentFlags::NOT_ALIGNED`]
+        const DATA_TAIL = 0x400;
+
+        /// Space is allocated, but not written
+        const UNWRITTEN = 0x800;
+
+        /// File does not natively support extents, the result was merged
+        /// from blocks
+        const MERGED = 0x1000;
+
+        /// Space is shared with other files
+        const SHARED = 0x2000;
+    }
+}
+
+/// A physical extent of a file, see [`FileExt::extents`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Extent {
+    /// Byte offset of the extent in the file
+    pub logical: u64,
+
+    /// Byte offset of the extent on disk
+    pub physical: u64,
+
+    /// Length of the extent in bytes
+    pub length: u64,
+
+    /// Extent flags
+    pub flags: ExtentFlags,
+}
+
 /// Type of lock to use for [`FileExt::lock`]
 #[derive(Debug, Copy, Clone)]
 pub enum LockType {
@@ -318,7 +533,100 @@ pub trait FileExt: AsRawFd {
         Ok(())
     }
 
-    // TODO: Dig holes, see `fallocate(1)`.
+    /// Deallocate, or punch a hole in, `len` bytes starting at `offset`.
+    ///
+    /// The file size is unchanged, and the hole reads back as zeros, but no
+    /// longer uses disk space.
+    ///
+    /// # Implementation
+    ///
+    /// This uses `fallocate(2)`, with `FALLOC_FL_PUNCH_HOLE` and
+    /// `FALLOC_FL_KEEP_SIZE`.
+    ///
+    /// This will retry as necessary on `EINTR`
+    ///
+    /// # Errors
+    ///
+    /// - [`io::ErrorKind::Unsupported`] if the filesystem doesn't support
+    ///   punching holes.
+    /// - If `self` is not opened for writing.
+    /// - If `self` is not a regular file.
+    /// - If I/O does.
+    ///
+    /// # Panics
+    ///
+    /// - If `len` is not positive
+    fn punch_hole(&self, offset: i64, len: i64) -> io::Result<()> {
+        assert!(len > 0, "Length must be positive");
+        let fd = self.as_raw_fd();
+        loop {
+            let e = fallocate(
+                fd,
+                FallocateFlags::FALLOC_FL_PUNCH_HOLE | FallocateFlags::FALLOC_FL_KEEP_SIZE,
+                offset,
+                len,
+            );
+            match e {
+                Ok(_) => break,
+                Err(Errno::EINTR) => continue,
+                Err(Errno::EOPNOTSUPP) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        "Filesystem does not support punching holes",
+                    ))
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy `len` bytes starting at `off_in` in `self` to `off_out` in `dst`,
+    /// without going through userspace.
+    ///
+    /// Depending on the filesystem this may be done by the filesystem or
+    /// storage itself, such as reflinks on btrfs/XFS, or server-side copies on
+    /// NFS.
+    ///
+    /// The file offsets of `self` and `dst` are not changed.
+    ///
+    /// Returns the number of bytes copied, which is less than `len` only if
+    /// the end of `self` was reached.
+    ///
+    /// # Implementation
+    ///
+    /// This uses `copy_file_range(2)`
+    ///
+    /// This will retry as necessary on `EINTR` and partial copies.
+    ///
+    /// # Errors
+    ///
+    /// - If `self` is not opened for reading, or `dst` for writing.
+    /// - If the copy is not supported, such as `EXDEV` or `ENOSYS`. These are
+    ///   returned unchanged, so you can fall back to a normal copy.
+    /// - If I/O does.
+    fn copy_range(&self, off_in: i64, dst: &File, off_out: i64, len: usize) -> io::Result<usize> {
+        let (fd_in, fd_out) = (self.as_raw_fd(), dst.as_raw_fd());
+        let (mut off_in, mut off_out) = (off_in, off_out);
+        let mut copied = 0;
+        while copied < len {
+            let e = copy_file_range(
+                fd_in,
+                Some(&mut off_in),
+                fd_out,
+                Some(&mut off_out),
+                len - copied,
+            );
+            match e {
+                // End of file
+                Ok(0) => break,
+                Ok(n) => copied += n,
+                Err(Errno::EINTR) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(copied)
+    }
 
     /// Tell the kernel to re-read the partition table.
     /// This call may be unreliable and require reboots.
@@ -362,6 +670,181 @@ pub trait FileExt: AsRawFd {
     /// - If `self` is not a block device.
     /// - If the underlying ioctl does.
     fn remove_partition(&self, part: i32) -> io::Result<()>;
+
+    /// Resize partition number `part`.
+    ///
+    /// The partition will start at `start` bytes and end at `end` bytes,
+    /// relative to the start of `self`.
+    ///
+    /// Unlike removing and adding the partition again, this works while the
+    /// partition is in use.
+    ///
+    /// # Implementation
+    ///
+    /// This uses the `BLKPG` ioctl.
+    ///
+    /// # Errors
+    ///
+    /// - If `self` is not a block device.
+    /// - If the underlying ioctl does.
+    fn resize_partition(&self, part: i32, start: i64, end: i64) -> io::Result<()>;
+
+    /// Logical sector size of the device, the smallest unit it can address.
+    ///
+    /// # Implementation
+    ///
+    /// This uses the `BLKSSZGET` ioctl.
+    ///
+    /// # Errors
+    ///
+    /// - If `self` is not a block device.
+    /// - If the underlying ioctl does.
+    fn logical_sector_size(&self) -> io::Result<u32>;
+
+    /// Physical block size of the device, the smallest unit it can write
+    /// atomically.
+    ///
+    /// # Implementation
+    ///
+    /// This uses the `BLKPBSZGET` ioctl.
+    ///
+    /// # Errors
+    ///
+    /// - If `self` is not a block device.
+    /// - If the underlying ioctl does.
+    fn physical_block_size(&self) -> io::Result<u32>;
+
+    /// Size of the device, in bytes.
+    ///
+    /// This comes directly from the kernel, and so is always current,
+    /// even while the device is being resized.
+    ///
+    /// # Implementation
+    ///
+    /// This uses the `BLKGETSIZE64` ioctl.
+    ///
+    /// # Errors
+    ///
+    /// - If `self` is not a block device.
+    /// - If the underlying ioctl does.
+    fn device_size(&self) -> io::Result<u64>;
+
+    /// Discard, or TRIM, `len` bytes starting at `offset`, telling the device
+    /// they're unused.
+    ///
+    /// Discarded data may or may not read back as zeros.
+    ///
+    /// # Implementation
+    ///
+    /// This uses the `BLKDISCARD` ioctl.
+    ///
+    /// # Errors
+    ///
+    /// - If `self` is not a block device.
+    /// - If the underlying ioctl does.
+    fn discard(&self, offset: u64, len: u64) -> io::Result<()>;
+
+    /// Like [`FileExt::discard`], but the discarded data must also be
+    /// securely erased, including any copies the device made.
+    ///
+    /// Many devices don't support this, and the kernel error, usually
+    /// `EOPNOTSUPP`, is returned unchanged so you can fall back to something
+    /// else.
+    ///
+    /// # Implementation
+    ///
+    /// This uses the `BLKSECDISCARD` ioctl.
+    ///
+    /// # Errors
+    ///
+    /// - If `self` is not a block device.
+    /// - If the underlying ioctl does.
+    fn secure_discard(&self, offset: u64, len: u64) -> io::Result<()>;
+
+    /// Whether the device is read-only.
+    ///
+    /// # Implementation
+    ///
+    /// This uses the `BLKROGET` ioctl.
+    ///
+    /// # Errors
+    ///
+    /// - If `self` is not a block device.
+    /// - If the underlying ioctl does.
+    fn is_read_only(&self) -> io::Result<bool>;
+
+    /// Set whether the device is read-only.
+    ///
+    /// This is done by the kernel, and applies to everything using the device,
+    /// not just `self`.
+    ///
+    /// # Implementation
+    ///
+    /// This uses the `BLKROSET` ioctl.
+    ///
+    /// # Errors
+    ///
+    /// - If `self` is not a block device.
+    /// - [`io::ErrorKind::PermissionDenied`] without `CAP_SYS_ADMIN`.
+    /// - If the underlying ioctl does.
+    fn set_read_only(&self, ro: bool) -> io::Result<()>;
+
+    /// Flush the kernels buffer cache for the device, writing dirty buffers
+    /// and dropping cached ones.
+    ///
+    /// This is useful after writing to the device directly, such as a new
+    /// partition table, so stale cached blocks aren't used.
+    ///
+    /// # Implementation
+    ///
+    /// This uses the `BLKFLSBUF` ioctl.
+    ///
+    /// # Errors
+    ///
+    /// - If `self` is not a block device.
+    /// - If the underlying ioctl does.
+    fn flush_buffers(&self) -> io::Result<()>;
+
+    /// Get the physical extents of the file, sorted by logical offset.
+    ///
+    /// Holes in sparse files have no extents.
+    ///
+    /// The file is synced first, so delayed allocations have a location.
+    ///
+    /// # Implementation
+    ///
+    /// This uses the `FS_IOC_FIEMAP` ioctl.
+    ///
+    /// # Errors
+    ///
+    /// - If the filesystem doesn't support `FS_IOC_FIEMAP`
+    /// - If the underlying ioctl does.
+    fn extents(&self) -> io::Result<Vec<Extent>> {
+        let fd = self.as_raw_fd();
+        let mut extents = Vec::new();
+        let mut start = 0;
+        loop {
+            let mut map = _impl::Fiemap::new(start);
+            unsafe { _impl::file_extent_map(fd, &mut map) }?;
+            let mapped = &map.extents[..map.mapped_extents as usize];
+            let last = match mapped.last() {
+                Some(last) => *last,
+                None => break,
+            };
+            extents.extend(mapped.iter().map(|e| Extent {
+                logical: e.logical,
+                physical: e.physical,
+                length: e.length,
+                flags: ExtentFlags::from_bits_truncate(e.flags),
+            }));
+            if last.flags & ExtentFlags::LAST.bits() != 0 {
+                break;
+            }
+            // Continue after the last extent we got
+            start = last.logical + last.length;
+        }
+        Ok(extents)
+    }
 }
 
 impl FileExt for File {
@@ -407,4 +890,132 @@ impl FileExt for File {
             Err(e) => Err(e.into()),
         }
     }
+
+    fn resize_partition(&self, part: i32, start: i64, end: i64) -> io::Result<()> {
+        if !self.metadata()?.file_type().is_block_device() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "File was not a block device",
+            ));
+        }
+        let mut part = _impl::BlockPagePartArgs::new(part, start, end);
+        let args = _impl::BlockPageIoctlArgs::new(_impl::BLOCK_RESIZE_PART, &mut part);
+        match unsafe { _impl::block_page(self.as_raw_fd(), &args) } {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn logical_sector_size(&self) -> io::Result<u32> {
+        if !self.metadata()?.file_type().is_block_device() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "File was not a block device",
+            ));
+        }
+        let mut size = 0;
+        match unsafe { _impl::block_logical_sector_size(self.as_raw_fd(), &mut size) } {
+            Ok(_) => Ok(size as u32),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn physical_block_size(&self) -> io::Result<u32> {
+        if !self.metadata()?.file_type().is_block_device() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "File was not a block device",
+            ));
+        }
+        let mut size = 0;
+        match unsafe { _impl::block_physical_block_size(self.as_raw_fd(), &mut size) } {
+            Ok(_) => Ok(size),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn device_size(&self) -> io::Result<u64> {
+        if !self.metadata()?.file_type().is_block_device() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "File was not a block device",
+            ));
+        }
+        let mut size = 0;
+        match unsafe { _impl::block_device_size(self.as_raw_fd(), &mut size) } {
+            Ok(_) => Ok(size),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn discard(&self, offset: u64, len: u64) -> io::Result<()> {
+        if !self.metadata()?.file_type().is_block_device() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "File was not a block device",
+            ));
+        }
+        match unsafe { _impl::block_discard(self.as_raw_fd(), &[offset, len]) } {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn secure_discard(&self, offset: u64, len: u64) -> io::Result<()> {
+        if !self.metadata()?.file_type().is_block_device() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "File was not a block device",
+            ));
+        }
+        match unsafe { _impl::block_secure_discard(self.as_raw_fd(), &[offset, len]) } {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn is_read_only(&self) -> io::Result<bool> {
+        if !self.metadata()?.file_type().is_block_device() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "File was not a block device",
+            ));
+        }
+        let mut ro = 0;
+        match unsafe { _impl::block_get_read_only(self.as_raw_fd(), &mut ro) } {
+            Ok(_) => Ok(ro != 0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set_read_only(&self, ro: bool) -> io::Result<()> {
+        if !self.metadata()?.file_type().is_block_device() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "File was not a block device",
+            ));
+        }
+        match unsafe { _impl::block_set_read_only(self.as_raw_fd(), &(ro as c_int)) } {
+            Ok(_) => Ok(()),
+            // Both mean we lack `CAP_SYS_ADMIN`
+            Err(Errno::EACCES) | Err(Errno::EPERM) => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Setting read-only requires CAP_SYS_ADMIN",
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn flush_buffers(&self) -> io::Result<()> {
+        if !self.metadata()?.file_type().is_block_device() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "File was not a block device",
+            ));
+        }
+        match unsafe { _impl::block_flush_buffers(self.as_raw_fd()) } {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
 }
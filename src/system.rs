@@ -1,8 +1,9 @@
 //! This module provides ways to access information from a running Linux system
-use std::collections::HashMap;
+use std::{collections::HashMap, io};
 
 pub mod devices;
 pub mod modules;
+pub mod uevent;
 
 /// Supported [`UEvent`] actions
 pub enum UEventAction {
@@ -19,8 +20,13 @@ pub enum UEventAction {
 /// [1]: https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-uevent
 pub trait UEvent {
     /// Write a synthetic `uevent`
-    fn write(&self, action: UEventAction, uuid: Option<String>, args: HashMap<String, String>);
+    fn write(
+        &self,
+        action: UEventAction,
+        uuid: Option<String>,
+        args: HashMap<String, String>,
+    ) -> io::Result<()>;
 
     /// Return the Key=Value pairs in the `uevent` file.
-    fn read(&self) -> HashMap<String, String>;
+    fn read(&self) -> io::Result<HashMap<String, String>>;
 }
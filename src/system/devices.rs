@@ -1,3 +1,9 @@
 //! This module provides ways to get information about connected devices
 
+pub mod backlight;
 pub mod block;
+pub mod hwmon;
+pub mod input;
+pub mod net;
+pub mod power_supply;
+pub mod thermal;
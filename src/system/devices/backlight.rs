@@ -0,0 +1,153 @@
+//! This module provides ways to get information about and control display
+//! backlights
+//!
+//! # Examples
+//!
+//! Set all backlights to half brightness
+//!
+//! ```rust,no_run
+//! # use linapi::system::devices::backlight::Backlight;
+//! for mut backlight in Backlight::get_connected().unwrap() {
+//!     backlight.set_percent(50.0).unwrap();
+//! }
+//! ```
+//!
+//! # Implementation
+//!
+//! This uses the sysfs interface, documented [here][1].
+//!
+//! [1]: https://www.kernel.org/doc/Documentation/ABI/stable/sysfs-class-backlight
+use crate::util::{attr_error, read_attr_parsed, SYSFS_PATH};
+use displaydoc::Display;
+use std::{
+    fs,
+    fs::DirEntry,
+    io,
+    io::prelude::*,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Backlight Error type
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// IO Failed
+    Io(#[from] io::Error),
+
+    /// Invalid argument: {0}
+    InvalidArg(&'static str),
+
+    /// The device or attribute was invalid
+    Invalid,
+
+    /// Permission denied, this requires root or write access to `brightness`
+    Permission,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A display backlight
+#[derive(Debug, Clone)]
+pub struct Backlight {
+    /// Kernel name
+    name: String,
+
+    /// Canonical, full, path to the device.
+    path: PathBuf,
+}
+
+// Public
+impl Backlight {
+    /// Get backlights.
+    ///
+    /// The returned Vec is sorted by kernel name.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Io`] for I/O errors
+    pub fn get_connected() -> Result<Vec<Self>> {
+        let path = Path::new(SYSFS_PATH).join("class/backlight");
+        let mut devices = Vec::new();
+        if !path.exists() {
+            return Ok(devices);
+        }
+        for dev in path.read_dir()? {
+            let dev: DirEntry = dev?;
+            devices.push(Self {
+                name: dev.file_name().into_string().map_err(|_| Error::Invalid)?,
+                path: dev.path().canonicalize()?,
+            });
+        }
+        devices.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        Ok(devices)
+    }
+
+    /// Kernel name for this backlight, such as `intel_backlight`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Canonical path to the backlight.
+    ///
+    /// You normally shouldn't need this, but it could be useful if
+    /// you want to manually access information not exposed by this crate.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Requested brightness, from `0` to [`Backlight::max_brightness`].
+    pub fn brightness(&self) -> Result<u64> {
+        read_attr_parsed(&self.path, "brightness").map_err(|e| attr_error(e, Error::Invalid))
+    }
+
+    /// Maximum brightness.
+    pub fn max_brightness(&self) -> Result<u64> {
+        read_attr_parsed(&self.path, "max_brightness").map_err(|e| attr_error(e, Error::Invalid))
+    }
+
+    /// Set the brightness, from `0` to [`Backlight::max_brightness`].
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArg`] if `brightness` is larger than
+    ///   [`Backlight::max_brightness`]
+    /// - [`Error::Permission`] if you don't have permission to change the
+    ///   brightness
+    /// - If I/O does
+    pub fn set_brightness(&mut self, brightness: u64) -> Result<()> {
+        if brightness > self.max_brightness()? {
+            return Err(Error::InvalidArg("Brightness was larger than the maximum"));
+        }
+        let write = || -> io::Result<()> {
+            let mut f = fs::OpenOptions::new()
+                .write(true)
+                .open(self.path.join("brightness"))?;
+            write!(f, "{}", brightness)
+        };
+        match write() {
+            Ok(()) => Ok(()),
+            Err(e) if matches!(e.raw_os_error(), Some(libc::EACCES | libc::EPERM)) => {
+                Err(Error::Permission)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Set the brightness as a percentage of [`Backlight::max_brightness`],
+    /// from `0.0` to `100.0`.
+    ///
+    /// See [`Backlight::set_brightness`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArg`] if `percent` is out of range
+    /// - See [`Backlight::set_brightness`]
+    pub fn set_percent(&mut self, percent: f32) -> Result<()> {
+        if !(0.0..=100.0).contains(&percent) {
+            return Err(Error::InvalidArg("Percent was out of range"));
+        }
+        let max = self.max_brightness()?;
+        let brightness = (max as f64 * f64::from(percent) / 100.0).round() as u64;
+        self.set_brightness(brightness.min(max))
+    }
+}
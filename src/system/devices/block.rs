@@ -1,21 +1,30 @@
 //! This module provides ways to get information about connected Block devices
 use crate::{
     extensions::FileExt,
-    util::{DEV_PATH, SYSFS_PATH},
+    util::{attr_error, read_attr_optional, read_attr_parsed, DEV_PATH, SYSFS_PATH},
 };
 use bitflags::bitflags;
 use displaydoc::Display;
-use nix::sys::stat;
+use nix::{ioctl_write_int_bad, libc::c_int, sys::stat};
 use std::{
     convert::TryInto,
+    fmt::Display,
     fs,
     fs::DirEntry,
     io,
     io::prelude::*,
     ops::Range,
-    os::{linux::fs::MetadataExt, unix::fs::FileTypeExt},
+    os::{
+        linux::fs::MetadataExt,
+        unix::{
+            fs::{FileTypeExt, OpenOptionsExt},
+            io::AsRawFd,
+        },
+    },
     path::{Path, PathBuf},
-    time::Duration,
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
 };
 use thiserror::Error;
 
@@ -30,10 +39,23 @@ pub enum Error {
 
     /// The device or attribute was invalid
     Invalid,
+
+    /// Couldn't apply tuning for: {0:?}
+    Tuning(Vec<&'static str>),
+
+    /// Permission denied, this requires root
+    Permission,
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+ioctl_write_int_bad!(
+    /// The `CDROM_MEDIA_CHANGED` ioctl, defined in
+    /// <linux/cdrom.h>
+    cdrom_media_changed,
+    0x5325
+);
+
 /// Parse the undocumented `dev` device attribute.
 ///
 /// This seems to be formatted as `major:minor\n`
@@ -76,14 +98,103 @@ fn find_from_major_minor(major: u64, minor: u64) -> Result<Option<PathBuf>> {
     Ok(None)
 }
 
+/// Read and parse the `queue/` attribute `attr` at `path`
+fn read_queue<T: FromStr>(path: &Path, attr: &str) -> Result<T> {
+    read_attr_parsed(path.join("queue"), attr).map_err(|e| attr_error(e, Error::Invalid))
+}
+
+/// Write `value` to the `queue/` attribute `attr` at `path`
+fn write_queue<T: Display>(path: &Path, attr: &str, value: T) -> Result<()> {
+    let mut f = fs::OpenOptions::new()
+        .write(true)
+        .open(path.join("queue").join(attr))?;
+    write!(f, "{}", value)?;
+    Ok(())
+}
+
+/// Parse the `queue/scheduler` attribute at `path`
+///
+/// This is formatted as `mq-deadline [none] kyber`, where the bracketed
+/// scheduler is the active one.
+///
+/// Returns the available schedulers and the active one.
+fn read_scheduler(path: &Path) -> Result<(Vec<String>, String)> {
+    let s = fs::read_to_string(path.join("queue/scheduler"))?;
+    let mut available = Vec::new();
+    let mut active = None;
+    for sched in s.split_whitespace() {
+        match sched.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Some(sched) => {
+                active = Some(sched.to_owned());
+                available.push(sched.to_owned());
+            }
+            None => available.push(sched.to_owned()),
+        }
+    }
+    // Devices without a scheduler just report `none`, no brackets.
+    let active = match active {
+        Some(active) => active,
+        None if available.len() == 1 => available[0].clone(),
+        None => return Err(Error::Invalid),
+    };
+    Ok((available, active))
+}
+
+/// Parse the `stat` attribute at `path`
+///
+/// This is a whitespace separated list of counters, 11 on older kernels,
+/// 15 since 4.18, and 17 since 5.5.
+fn read_stats(path: &Path) -> Result<BlockStats> {
+    let s = fs::read_to_string(path.join("stat"))?;
+    let v = s
+        .split_whitespace()
+        .map(|s| s.parse::<u64>().map_err(|_| Error::Invalid))
+        .collect::<Result<Vec<_>>>()?;
+    if v.len() < 11 {
+        return Err(Error::Invalid);
+    }
+    let get = |i: usize| v.get(i).copied();
+    Ok(BlockStats {
+        read_ios: v[0],
+        read_merges: v[1],
+        read_sectors: v[2],
+        read_ticks: v[3],
+        write_ios: v[4],
+        write_merges: v[5],
+        write_sectors: v[6],
+        write_ticks: v[7],
+        in_flight: v[8],
+        io_ticks: v[9],
+        time_in_queue: v[10],
+        discard_ios: get(11),
+        discard_merges: get(12),
+        discard_sectors: get(13),
+        discard_ticks: get(14),
+        flush_ios: get(15),
+        flush_ticks: get(16),
+    })
+}
+
+/// Read the first of `paths` that exists, trimmed.
+///
+/// [`None`] is returned if none of them exist.
+fn read_first(paths: &[PathBuf]) -> Result<Option<String>> {
+    for path in paths {
+        match fs::read_to_string(path) {
+            Ok(s) => return Ok(Some(s.trim().to_owned())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(None)
+}
+
 fn dev_size(path: &Path) -> Result<u64> {
-    fs::read_to_string(path.join("size"))?
-        .trim()
-        .parse::<u64>()
+    read_attr_parsed::<u64, _>(path, "size")
         // Per [this][1] forgotten 2015 patch, this is in 512 byte sectors.
         // [1]: https://lore.kernel.org/lkml/1451154995-4686-1-git-send-email-peter@lekensteyn.nl/
         .map(|b| b * 512)
-        .map_err(|_| Error::Invalid)
+        .map_err(|e| attr_error(e, Error::Invalid))
 }
 
 bitflags! {
@@ -133,6 +244,21 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Flags corresponding to [`Block::media_events`].
+    ///
+    /// See the [linux kernel docs][1] for details.
+    ///
+    /// [1]: https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-block
+    pub struct MediaEvents: u32 {
+        /// The media in the device changed
+        const MEDIA_CHANGE = 1;
+
+        /// The eject button on the device was pressed
+        const EJECT_REQUEST = 2;
+    }
+}
+
 /// A Block Device
 #[derive(Debug, Clone)]
 pub struct Block {
@@ -214,6 +340,28 @@ impl Block {
         Self::new(path)
     }
 
+    /// Create from the devices sysfs directory.
+    ///
+    /// This is useful if you already have the path, such as from
+    /// [`UEventMessage::sys_path`][crate::system::uevent::UEventMessage::sys_path].
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArg`] if `path` is not a block device
+    /// - [`Error::InvalidArg`] if `path` is a partition
+    /// - [`Error::Io`] for I/O errors
+    pub fn from_sys_path(path: &Path) -> Result<Self> {
+        let path = path.canonicalize()?;
+        let subsystem = fs::read_link(path.join("subsystem"))?;
+        if subsystem.file_name().and_then(|s| s.to_str()) != Some("block") {
+            return Err(Error::InvalidArg("path"));
+        }
+        if path.join("partition").exists() {
+            return Err(Error::InvalidArg("path"));
+        }
+        Self::new(path)
+    }
+
     /// Canonical path to the block device.
     ///
     /// You normally shouldn't need this, but it could be useful if
@@ -252,6 +400,64 @@ impl Block {
         Ok(devices)
     }
 
+    /// Get partition number `num` of this device, if it exists.
+    ///
+    /// # Implementation
+    ///
+    /// The kernel names partitions predictably, `sda` becomes `sda1`, and
+    /// devices whose name ends in a digit get a `p`, so `nvme0n1` becomes
+    /// `nvme0n1p1`. That path is checked directly, falling back to searching
+    /// [`Block::partitions`] for drivers that name them differently.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    pub fn partition(&self, num: u64) -> Result<Option<Partition>> {
+        let name = if self.name.ends_with(|c: char| c.is_ascii_digit()) {
+            format!("{}p{}", self.name, num)
+        } else {
+            format!("{}{}", self.name, num)
+        };
+        let path = self.path.join(name);
+        if path.join("partition").exists() {
+            let part = Partition::new(path)?;
+            if part.number()? == num {
+                return Ok(Some(part));
+            }
+        }
+        for part in self.partitions()? {
+            if part.number()? == num {
+                return Ok(Some(part));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the devices this device is built on top of, such as the disks
+    /// under a device-mapper or md RAID device.
+    ///
+    /// # Note
+    ///
+    /// These may be partitions, which are returned as a [`Block`] describing
+    /// only that partition.
+    ///
+    /// The returned Vec is sorted by kernel name.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    pub fn slaves(&self) -> Result<Vec<Block>> {
+        Self::stacked(&self.path, "slaves")
+    }
+
+    /// Get the devices built on top of this device, such as LVM logical
+    /// volumes or dm-crypt devices.
+    ///
+    /// See [`Block::slaves`] for more details.
+    pub fn holders(&self) -> Result<Vec<Block>> {
+        Self::stacked(&self.path, "holders")
+    }
+
     /// Open the device special file in `/dev` associated with this block
     /// device, if it exists.
     ///
@@ -270,6 +476,39 @@ impl Block {
         }
     }
 
+    /// Open the device special file in `/dev` associated with this block
+    /// device, if it exists.
+    ///
+    /// The device file is opened only for reading, which is safer for
+    /// inspection and doesn't require write access to the device.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    pub fn open_readonly(&self) -> Result<Option<fs::File>> {
+        let path = find_from_major_minor(self.major, self.minor)?;
+        match path {
+            Some(path) => Ok(Some(fs::File::open(path)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Set whether the device is read-only, such as before doing forensic
+    /// reads.
+    ///
+    /// The device special file is opened read-only, so this works on devices
+    /// that are already read-only.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Invalid`] if the device special file doesn't exist
+    /// - If the ioctl does, see [`FileExt::set_read_only`]
+    pub fn set_read_only(&mut self, ro: bool) -> Result<()> {
+        let f = self.open_readonly()?.ok_or(Error::Invalid)?;
+        f.set_read_only(ro)?;
+        Ok(())
+    }
+
     /// Device major number
     pub fn major(&self) -> u64 {
         self.major
@@ -285,27 +524,115 @@ impl Block {
         dev_size(&self.path)
     }
 
+    /// Get the byte size of the device, from the kernel.
+    ///
+    /// Unlike [`Block::size`], this is always current, even for devices such
+    /// as loop or device-mapper targets that are being resized.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Invalid`] if the device special file doesn't exist
+    /// - If the ioctl does
+    ///
+    /// # Implementation
+    ///
+    /// This uses [`FileExt::device_size`].
+    pub fn size_bytes_ioctl(&self) -> Result<u64> {
+        let f = self.open_readonly()?.ok_or(Error::Invalid)?;
+        Ok(f.device_size()?)
+    }
+
     /// Get device capabilities.
     ///
     /// Unknown flags *are* preserved
     ///
     /// See [`BlockCap`] for more details.
     pub fn capability(&self) -> Result<BlockCap> {
+        // This is a hex word, without a prefix, but accept one anyway.
+        let cap = std::fs::read_to_string(self.path.join("capability"))?;
+        let cap = cap.trim();
+        let cap = u32::from_str_radix(cap.strip_prefix("0x").unwrap_or(cap), 16)
+            .map_err(|_| Error::Invalid)?;
         // Unknown bits are safe, and the kernel may add new flags.
-        Ok(unsafe {
-            BlockCap::from_bits_unchecked(
-                std::fs::read_to_string(self.path.join("capability"))?
-                    .trim()
-                    .parse()
-                    .map_err(|_| Error::Invalid)?,
-            )
-        })
+        Ok(unsafe { BlockCap::from_bits_unchecked(cap) })
+    }
+
+    /// Get the media events this device supports.
+    ///
+    /// This reads the `events` attribute, which lists the events the kernel
+    /// will report for this device, through uevents and by polling.
+    ///
+    /// Devices with [`BlockCap::MEDIA_CHANGE_NOTIFY`] report these
+    /// asynchronously, others may have to be polled by the kernel, see
+    /// `events_poll_msecs`.
+    ///
+    /// Unknown events are ignored.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    pub fn media_events(&self) -> Result<MediaEvents> {
+        let mut events = MediaEvents::empty();
+        for event in fs::read_to_string(self.path.join("events"))?.split_whitespace() {
+            match event {
+                "media_change" => events |= MediaEvents::MEDIA_CHANGE,
+                "eject_request" => events |= MediaEvents::EJECT_REQUEST,
+                _ => (),
+            }
+        }
+        Ok(events)
+    }
+
+    /// Whether the device has media present.
+    ///
+    /// This is for devices with [`BlockCap::REMOVABLE`], such as card readers
+    /// and CD drives, which report a size of zero without media.
+    /// Devices with permanent media always have media present.
+    ///
+    /// # Implementation
+    ///
+    /// This reads the `size` attribute.
+    pub fn media_present(&self) -> Result<bool> {
+        Ok(self.size()? != 0)
+    }
+
+    /// Check whether the media has changed since this was last called,
+    /// by anyone.
+    ///
+    /// Devices with [`BlockCap::MEDIA_CHANGE_NOTIFY`] report media changes
+    /// through uevents and don't need polling.
+    /// Only devices with [`BlockCap::CD`] support this, for other devices use
+    /// [`Block::media_present`].
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArg`] if the device is not CD-like
+    /// - [`Error::Invalid`] if the device special file doesn't exist
+    /// - If the ioctl does
+    ///
+    /// # Implementation
+    ///
+    /// This uses the `CDROM_MEDIA_CHANGED` ioctl from `include/linux/cdrom.h`
+    pub fn poll_media_change(&self) -> Result<bool> {
+        if !self.capability()?.contains(BlockCap::CD) {
+            return Err(Error::InvalidArg("Device was not CD-like"));
+        }
+        let path = find_from_major_minor(self.major, self.minor)?.ok_or(Error::Invalid)?;
+        // Non-blocking so this works without media
+        let f = fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)?;
+        // `CDSL_CURRENT`, the current slot
+        let changed =
+            unsafe { cdrom_media_changed(f.as_raw_fd(), c_int::MAX) }.map_err(io::Error::from)?;
+        Ok(changed == 1)
     }
 
     /// Get device power information
     ///
     /// See [`Power`] for details
-    pub fn power(&self) -> Power {
+    pub fn power(&self) -> Power<'_> {
         Power::new(&self.path)
     }
 
@@ -375,6 +702,40 @@ impl Block {
         Ok(())
     }
 
+    /// Tell Linux partition `num` now covers `start_end`, in bytes.
+    ///
+    /// This works even if the partition is in use, unlike
+    /// [`Block::remove_partition`] followed by [`Block::add_partition`].
+    ///
+    /// See [`Block::add_partition`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Io`] if the ioctl fails, such as with `EBUSY` if the new
+    ///   range overlaps another partition.
+    ///
+    /// # Examples
+    ///
+    /// Grow a partition to cover the rest of the device
+    ///
+    /// ```rust,no_run
+    /// # use linapi::system::devices::block::Block;
+    /// let mut block = Block::get_connected().unwrap().remove(0);
+    /// let part = block.partitions().unwrap().remove(0);
+    /// let start = part.start().unwrap() as i64;
+    /// block.resize_partition(part.number().unwrap(), start..block.size().unwrap() as i64);
+    /// ```
+    pub fn resize_partition(&mut self, num: u64, start_end: Range<i64>) -> Result<()> {
+        let f = self.open()?.ok_or(Error::Invalid)?;
+        f.resize_partition(
+            num.try_into()
+                .map_err(|_| Error::InvalidArg("Partition number was too large"))?,
+            start_end.start,
+            start_end.end,
+        )?;
+        Ok(())
+    }
+
     /// Convenience function for looping through [`Block::partitions`] yourself.
     ///
     /// # Implementation
@@ -402,21 +763,364 @@ impl Block {
         // Note that this file is mostly undocumented, at this location,
         // but see [here][1] for more details
         // [1]: https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-bus-pci-devices-cciss
-        let path = self.path.parent().unwrap().parent().unwrap().join("model");
-        if !path.exists() {
-            return Ok(None);
-        }
-        Ok(Some(fs::read_to_string(path).map(|s| s.trim().to_owned())?))
+        let path = self.path.parent().unwrap().parent().unwrap();
+        Ok(read_attr_optional(path, "model")?)
+    }
+
+    /// Get the device World Wide Identifier, if it exists.
+    ///
+    /// This is a stable identifier for the device, used for
+    /// `/dev/disk/by-id/wwn-*` links.
+    pub fn wwid(&self) -> Result<Option<String>> {
+        // NVMe has this on the namespace, SCSI on the device
+        read_first(&[self.path.join("wwid"), self.path.join("device/wwid")])
+    }
+
+    /// Get the device serial number, if it exists.
+    pub fn serial(&self) -> Result<Option<String>> {
+        // Unwraps should be okay, always a parent.
+        // See `Block::model`, the serial is usually next to it.
+        read_first(&[
+            self.path.join("serial"),
+            self.path.join("device/serial"),
+            self.path.parent().unwrap().parent().unwrap().join("serial"),
+        ])
+    }
+
+    /// Get the device-mapper name, such as an LVM volume or LUKS container
+    /// name, for `dm-N` devices.
+    ///
+    /// Returns [`None`] if this isn't a device-mapper device.
+    pub fn dm_name(&self) -> Result<Option<String>> {
+        Ok(read_attr_optional(self.path.join("dm"), "name")?)
+    }
+
+    /// Get the device-mapper UUID, such as `LVM-...` or `CRYPT-LUKS2-...`,
+    /// for `dm-N` devices.
+    ///
+    /// Returns [`None`] if this isn't a device-mapper device, or it has no
+    /// UUID.
+    pub fn dm_uuid(&self) -> Result<Option<String>> {
+        Ok(read_attr_optional(self.path.join("dm"), "uuid")?.filter(|s| !s.is_empty()))
     }
 
     /// Device logical block size, the smallest unit the device can address.
     ///
     /// This is usually 512
     pub fn logical_block_size(&self) -> Result<u64> {
-        fs::read_to_string(self.path.join("queue/logical_block_size"))?
-            .trim()
-            .parse::<u64>()
-            .map_err(|_| Error::Invalid)
+        read_queue(&self.path, "logical_block_size")
+    }
+
+    /// Device physical block size, the smallest unit the device can write
+    /// atomically.
+    ///
+    /// Partitions should be aligned to this.
+    pub fn physical_block_size(&self) -> Result<u64> {
+        read_queue(&self.path, "physical_block_size")
+    }
+
+    /// Get I/O statistics for the device.
+    ///
+    /// See [`BlockStats`] for more details.
+    pub fn stats(&self) -> Result<BlockStats> {
+        read_stats(&self.path)
+    }
+
+    /// Smallest unit the device can discard, in bytes.
+    ///
+    /// `0` if the device doesn't support discard.
+    pub fn discard_granularity(&self) -> Result<u64> {
+        read_queue(&self.path, "discard_granularity")
+    }
+
+    /// Maximum size of a single discard, in bytes.
+    ///
+    /// `0` if the device doesn't support discard, or it's disabled.
+    pub fn discard_max_bytes(&self) -> Result<u64> {
+        read_queue(&self.path, "discard_max_bytes")
+    }
+
+    /// Whether discarded data is guaranteed to read back as zeroes.
+    ///
+    /// # Note
+    ///
+    /// Since Linux 4.12 this is always `false`, use
+    /// [`QueueLimits::write_zeroes_max_bytes`] to check if the device can
+    /// zero efficiently.
+    pub fn discard_zeroes_data(&self) -> Result<bool> {
+        match read_queue::<u8>(&self.path, "discard_zeroes_data")? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error::Invalid),
+        }
+    }
+
+    /// Get the I/O limits of the device queue.
+    ///
+    /// See [`QueueLimits`] for more details.
+    pub fn queue_limits(&self) -> Result<QueueLimits> {
+        let path = self.path.join("queue");
+        let get = |attr| match read_attr_optional(&path, attr)? {
+            Some(s) => s.parse().map(Some).map_err(|_| Error::Invalid),
+            None => Ok(None),
+        };
+        Ok(QueueLimits {
+            max_sectors_kb: get("max_sectors_kb")?,
+            max_hw_sectors_kb: get("max_hw_sectors_kb")?,
+            nr_requests: get("nr_requests")?,
+            discard_granularity: get("discard_granularity")?,
+            discard_max_bytes: get("discard_max_bytes")?,
+            write_zeroes_max_bytes: get("write_zeroes_max_bytes")?,
+            read_ahead_kb: get("read_ahead_kb")?,
+        })
+    }
+
+    /// Get the number of in-progress `(reads, writes)` for the device.
+    ///
+    /// Returns [`None`] if the kernel doesn't provide this.
+    pub fn inflight(&self) -> Result<Option<(u64, u64)>> {
+        let s = match read_attr_optional(&self.path, "inflight")
+            .map_err(|e| attr_error(e, Error::Invalid))?
+        {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        let mut i = s
+            .split_whitespace()
+            .map(|s| s.parse::<u64>().map_err(|_| Error::Invalid));
+        match (i.next(), i.next()) {
+            (Some(r), Some(w)) => Ok(Some((r?, w?))),
+            _ => Err(Error::Invalid),
+        }
+    }
+
+    /// Get the available I/O schedulers, and the active one.
+    ///
+    /// Devices without a scheduler report only `none`.
+    pub fn scheduler(&self) -> Result<(Vec<String>, String)> {
+        read_scheduler(&self.path)
+    }
+
+    /// Set the active I/O scheduler to `name`.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArg`] if `name` isn't an available scheduler
+    /// - If I/O does
+    pub fn set_scheduler(&mut self, name: &str) -> Result<()> {
+        match write_queue(&self.path, "scheduler", name) {
+            Err(Error::Io(e)) if e.raw_os_error() == Some(libc::EINVAL) => {
+                Err(Error::InvalidArg("Scheduler was not available"))
+            }
+            r => r,
+        }
+    }
+
+    /// Get the amount of data the kernel reads ahead for sequential reads,
+    /// in KiB.
+    pub fn read_ahead_kb(&self) -> Result<u64> {
+        read_queue(&self.path, "read_ahead_kb")
+    }
+
+    /// Set the amount of data the kernel reads ahead for sequential reads,
+    /// in KiB.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Permission`] if you don't have permission to change the
+    ///   read-ahead
+    /// - If I/O does
+    pub fn set_read_ahead_kb(&mut self, kb: u64) -> Result<()> {
+        match write_queue(&self.path, "read_ahead_kb", kb) {
+            Err(Error::Io(e)) if matches!(e.raw_os_error(), Some(libc::EACCES | libc::EPERM)) => {
+                Err(Error::Permission)
+            }
+            r => r,
+        }
+    }
+
+    /// Get the zoned model of the device, for SMR hard drives and ZNS SSDs.
+    pub fn zoned_model(&self) -> Result<ZonedModel> {
+        let model = fs::read_to_string(self.path.join("queue/zoned"))?;
+        Ok(match model.trim() {
+            "none" => ZonedModel::None,
+            "host-managed" => ZonedModel::HostManaged,
+            "host-aware" => ZonedModel::HostAware,
+            model => ZonedModel::Unknown(model.into()),
+        })
+    }
+
+    /// Get the number of zones on the device.
+    ///
+    /// This is zero for devices that aren't zoned, and [`None`] if the kernel
+    /// doesn't support reporting it.
+    pub fn nr_zones(&self) -> Result<Option<u64>> {
+        match read_queue(&self.path, "nr_zones") {
+            Ok(n) => Ok(Some(n)),
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether the device is rotational, such as a spinning hard disk,
+    /// rather than an SSD.
+    pub fn is_rotational(&self) -> Result<bool> {
+        match read_queue::<u8>(&self.path, "rotational")? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error::Invalid),
+        }
+    }
+
+    /// Detect the partition table on the device, if any.
+    ///
+    /// This reads the device directly, not the kernels view of it, so it can
+    /// be used to decide whether to use [`Block::add_partition`] yourself.
+    ///
+    /// # Note
+    ///
+    /// Only the signatures are checked, not the partition table itself.
+    ///
+    /// Some filesystems, such as FAT, also have the MBR signature, and so
+    /// unpartitioned devices containing them may be detected as
+    /// [`PartitionTable::Mbr`].
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Invalid`] if the device special file doesn't exist
+    /// - If I/O does
+    pub fn partition_table(&self) -> Result<Option<PartitionTable>> {
+        let lba = self.logical_block_size()?;
+        if lba < 512 {
+            return Err(Error::Invalid);
+        }
+        let mut f = self.open_readonly()?.ok_or(Error::Invalid)?;
+        // The MBR is at LBA 0, and the GPT header at LBA 1.
+        let mut data = vec![0; (lba * 2).try_into().map_err(|_| Error::Invalid)?];
+        match f.read_exact(&mut data) {
+            Ok(()) => (),
+            // Too small to have a partition table
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let gpt = &data[lba as usize..];
+        if gpt.starts_with(b"EFI PART") {
+            Ok(Some(PartitionTable::Gpt))
+        } else if data[510..512] == [0x55, 0xAA] {
+            Ok(Some(PartitionTable::Mbr))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Byte ranges of the device not covered by any partition.
+    ///
+    /// The returned ranges are sorted, don't overlap, and are aligned to
+    /// [`Block::logical_block_size`]. Ranges too small to hold a single
+    /// logical block are not included.
+    ///
+    /// # Note
+    ///
+    /// This is based only on the kernels view of the partitions, space used by
+    /// the partition table itself, such as the first and last sectors of a GPT
+    /// disk, will be reported as free.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    /// - [`Error::Invalid`] if the device or any of it's partitions are invalid
+    pub fn free_regions(&self) -> Result<Vec<Range<u64>>> {
+        let size = self.size()?;
+        let align = self.logical_block_size()?;
+        if align == 0 {
+            return Err(Error::Invalid);
+        }
+        let mut used = Vec::new();
+        for part in self.partitions()? {
+            let start = part.start()?;
+            used.push(start..start + part.size()?);
+        }
+        used.sort_unstable_by_key(|r| r.start);
+        //
+        let mut free = Vec::new();
+        let mut push_free = |range: Range<u64>| {
+            let start = range.start.div_ceil(align) * align;
+            let end = range.end.min(size) / align * align;
+            if start < end {
+                free.push(start..end);
+            }
+        };
+        let mut cursor = 0;
+        for range in used {
+            if range.start > cursor {
+                push_free(cursor..range.start);
+            }
+            cursor = cursor.max(range.end);
+        }
+        if cursor < size {
+            push_free(cursor..size);
+        }
+        Ok(free)
+    }
+
+    /// Get the current queue tuning for this device.
+    ///
+    /// See [`BlockTuning`] for details.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    /// - [`Error::Invalid`] if any of the attributes are invalid
+    pub fn tuning(&self) -> Result<BlockTuning> {
+        Ok(BlockTuning {
+            scheduler: read_scheduler(&self.path)?.1,
+            nr_requests: read_queue(&self.path, "nr_requests")?,
+            read_ahead_kb: read_queue(&self.path, "read_ahead_kb")?,
+            rotational: self.is_rotational()?,
+        })
+    }
+
+    /// Apply `tuning` to this device.
+    ///
+    /// Only the settings that differ from the current ones are written.
+    /// [`BlockTuning::rotational`] is ignored.
+    ///
+    /// Settings are validated before anything is written, but this is not
+    /// atomic, if writing one setting fails the others are still attempted.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArg`] if the scheduler isn't available for this
+    ///   device
+    /// - [`Error::Tuning`] with the names of the settings that failed to be
+    ///   written
+    /// - See [`Block::tuning`]
+    pub fn apply_tuning(&mut self, tuning: &BlockTuning) -> Result<()> {
+        let current = self.tuning()?;
+        let (available, _) = read_scheduler(&self.path)?;
+        if !available.contains(&tuning.scheduler) {
+            return Err(Error::InvalidArg("scheduler"));
+        }
+        let mut failed = Vec::new();
+        if current.scheduler != tuning.scheduler
+            && write_queue(&self.path, "scheduler", &tuning.scheduler).is_err()
+        {
+            failed.push("scheduler");
+        }
+        if current.nr_requests != tuning.nr_requests
+            && write_queue(&self.path, "nr_requests", tuning.nr_requests).is_err()
+        {
+            failed.push("nr_requests");
+        }
+        if current.read_ahead_kb != tuning.read_ahead_kb
+            && write_queue(&self.path, "read_ahead_kb", tuning.read_ahead_kb).is_err()
+        {
+            failed.push("read_ahead_kb");
+        }
+        if !failed.is_empty() {
+            return Err(Error::Tuning(failed));
+        }
+        Ok(())
     }
 }
 
@@ -435,6 +1139,174 @@ impl Block {
             minor,
         })
     }
+
+    /// Resolve the symlinks in the `dir` directory at `path`, such as
+    /// `slaves` or `holders`.
+    fn stacked(path: &Path, dir: &str) -> Result<Vec<Block>> {
+        let path = path.join(dir);
+        let mut devices = Vec::new();
+        if !path.exists() {
+            return Ok(devices);
+        }
+        for dev in path.read_dir()? {
+            let dev: DirEntry = dev?;
+            devices.push(Self::new(dev.path().canonicalize()?)?);
+        }
+        devices.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        Ok(devices)
+    }
+}
+
+/// Block device queue tuning.
+///
+/// See [`Block::tuning`] and [`Block::apply_tuning`].
+///
+/// See the [linux kernel docs][1] for details on these.
+///
+/// [1]: https://www.kernel.org/doc/html/latest/block/queue-sysfs.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockTuning {
+    /// The active I/O scheduler.
+    pub scheduler: String,
+
+    /// Maximum number of read and write requests that can be queued.
+    pub nr_requests: u64,
+
+    /// Maximum number of kilobytes to read-ahead.
+    pub read_ahead_kb: u64,
+
+    /// Whether the device is rotational.
+    ///
+    /// This is read-only, and ignored by [`Block::apply_tuning`].
+    pub rotational: bool,
+}
+
+/// Block device I/O statistics.
+///
+/// Sectors are always 512 bytes, regardless of the device, and ticks are in
+/// milliseconds.
+///
+/// Fields that are [`None`] aren't supported by the running kernel.
+///
+/// See the [linux kernel docs][1] for details on these.
+///
+/// [1]: https://www.kernel.org/doc/html/latest/block/stat.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockStats {
+    /// Number of read I/Os processed
+    pub read_ios: u64,
+
+    /// Number of read I/Os merged with in-queue I/O
+    pub read_merges: u64,
+
+    /// Number of sectors read
+    pub read_sectors: u64,
+
+    /// Total wait time for read requests
+    pub read_ticks: u64,
+
+    /// Number of write I/Os processed
+    pub write_ios: u64,
+
+    /// Number of write I/Os merged with in-queue I/O
+    pub write_merges: u64,
+
+    /// Number of sectors written
+    pub write_sectors: u64,
+
+    /// Total wait time for write requests
+    pub write_ticks: u64,
+
+    /// Number of I/Os currently in flight
+    pub in_flight: u64,
+
+    /// Total time this device has been active
+    pub io_ticks: u64,
+
+    /// Total wait time for all requests
+    pub time_in_queue: u64,
+
+    /// Number of discard I/Os processed
+    pub discard_ios: Option<u64>,
+
+    /// Number of discard I/Os merged with in-queue I/O
+    pub discard_merges: Option<u64>,
+
+    /// Number of sectors discarded
+    pub discard_sectors: Option<u64>,
+
+    /// Total wait time for discard requests
+    pub discard_ticks: Option<u64>,
+
+    /// Number of flush I/Os processed
+    pub flush_ios: Option<u64>,
+
+    /// Total wait time for flush requests
+    pub flush_ticks: Option<u64>,
+}
+
+/// Block device queue limits, see [`Block::queue_limits`].
+///
+/// Fields that are [`None`] aren't supported by the running kernel.
+///
+/// See the [linux kernel docs][1] for details on these.
+///
+/// [1]: https://www.kernel.org/doc/html/latest/block/queue-sysfs.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueLimits {
+    /// Maximum size of a request, in KiB
+    pub max_sectors_kb: Option<u64>,
+
+    /// Maximum size of a request the hardware supports, in KiB
+    pub max_hw_sectors_kb: Option<u64>,
+
+    /// Maximum number of requests that can be queued
+    pub nr_requests: Option<u64>,
+
+    /// Smallest unit that can be discarded, in bytes.
+    ///
+    /// `0` if discard isn't supported.
+    pub discard_granularity: Option<u64>,
+
+    /// Maximum size of a single discard, in bytes
+    pub discard_max_bytes: Option<u64>,
+
+    /// Maximum size of a single write zeroes request, in bytes
+    pub write_zeroes_max_bytes: Option<u64>,
+
+    /// Amount of data to read ahead, in KiB
+    pub read_ahead_kb: Option<u64>,
+}
+
+/// Partition table type, see [`Block::partition_table`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PartitionTable {
+    /// Master Boot Record, or DOS, partition table
+    Mbr,
+
+    /// GUID Partition Table
+    Gpt,
+}
+
+/// Zoned block device model, see [`Block::zoned_model`].
+///
+/// See the [linux kernel docs][1] for details on these.
+///
+/// [1]: https://www.kernel.org/doc/html/latest/block/queue-sysfs.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZonedModel {
+    /// Not a zoned device
+    None,
+
+    /// Zoned device that requires sequential writes within a zone
+    HostManaged,
+
+    /// Zoned device that supports random writes, but performs best with
+    /// sequential writes
+    HostAware,
+
+    /// A model unknown to this crate
+    Unknown(String),
 }
 
 /// A partition
@@ -468,6 +1340,17 @@ impl Partition {
         }
     }
 
+    /// Open the device file for this partition, only for reading.
+    ///
+    /// See [`Block::open_readonly`] for details
+    pub fn open_readonly(&self) -> Result<Option<fs::File>> {
+        let path = find_from_major_minor(self.major, self.minor)?;
+        match path {
+            Some(path) => Ok(Some(fs::File::open(path)?)),
+            None => Ok(None),
+        }
+    }
+
     /// Get the byte size of the device, if possible.
     pub fn size(&self) -> Result<u64> {
         dev_size(&self.path)
@@ -508,10 +1391,30 @@ impl Partition {
     pub fn number(&self) -> Result<u64> {
         // Note that this file is undocumented, but seems to contain the partition
         // number.
-        fs::read_to_string(self.path.join("partition"))?
-            .trim()
-            .parse::<u64>()
-            .map_err(|_| Error::Invalid)
+        read_attr_parsed(&self.path, "partition").map_err(|e| attr_error(e, Error::Invalid))
+    }
+
+    /// Get the [`Block`] device this partition is on.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    /// - [`Error::Invalid`] if the parent device is invalid
+    ///
+    /// # Examples
+    ///
+    /// A partitions parent is the device it came from
+    ///
+    /// ```rust,no_run
+    /// # use linapi::system::devices::block::Block;
+    /// let block = Block::get_connected().unwrap().remove(0);
+    /// let part = block.partitions().unwrap().remove(0);
+    /// assert_eq!(part.parent().unwrap().name(), block.name());
+    /// ```
+    pub fn parent(&self) -> Result<Block> {
+        // Partitions are always in their parents directory
+        let parent = self.path.parent().ok_or(Error::Invalid)?;
+        Block::new(parent.to_path_buf())
     }
 }
 
@@ -613,6 +1516,31 @@ impl Power<'_> {
         }
     }
 
+    /// Wait up to `timeout` for the runtime power status to become `target`.
+    ///
+    /// This is useful after [`Power::set_control`], which only allows the
+    /// device to suspend, to confirm it actually did.
+    ///
+    /// Returns whether `target` was reached.
+    ///
+    /// # Implementation
+    ///
+    /// This polls [`Power::status`] every 50 milliseconds.
+    pub fn wait_for_status(&self, target: Status, timeout: Duration) -> Result<bool> {
+        const INTERVAL: Duration = Duration::from_millis(50);
+        let start = Instant::now();
+        loop {
+            if self.status()? == target {
+                return Ok(true);
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Ok(false);
+            }
+            thread::sleep(INTERVAL.min(timeout - elapsed));
+        }
+    }
+
     /// Current auto-suspend delay, if supported.
     pub fn autosuspend_delay(&self) -> Result<Option<Duration>> {
         let f = fs::read_to_string(self.path.join("power/autosuspend_delay_ms"));
@@ -633,12 +1561,7 @@ impl Power<'_> {
         let mut f = fs::OpenOptions::new()
             .write(true)
             .open(self.path.join("power/autosuspend_delay_ms"))?;
-        let f = write!(f, "{}", delay.as_millis());
-        if let nix::errno::Errno::EIO = nix::Error::last() {
-            return Ok(None);
-        }
-        f?;
-        Ok(Some(()))
+        Self::autosuspend_result(write!(f, "{}", delay.as_millis()))
     }
 
     /// Whether the device is suspended/resumed asynchronously, during
@@ -659,7 +1582,7 @@ impl Power<'_> {
     /// [`Some`] is returned.
     ///
     /// If the Device does not support this, [`None`] is returned.
-    pub fn wakeup(&self) -> Option<Wakeup> {
+    pub fn wakeup(&self) -> Option<Wakeup<'_>> {
         let path = self.path.join("power/wakeup");
         if !path.exists() {
             return None;
@@ -673,6 +1596,17 @@ impl<'a> Power<'a> {
     fn new(path: &'a Path) -> Self {
         Self { path }
     }
+
+    /// Map the result of writing `power/autosuspend_delay_ms`.
+    ///
+    /// The kernel returns `EIO` if auto-suspend isn't supported.
+    fn autosuspend_result(res: io::Result<()>) -> Result<Option<()>> {
+        match res {
+            Ok(()) => Ok(Some(())),
+            Err(e) if e.raw_os_error() == Some(libc::EIO) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
 }
 
 /// Device wakeup information
@@ -802,3 +1736,44 @@ wakeup_helper_d!(
     prevent_sleep_time,
     "wakeup_prevent_sleep_time_ms"
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    /// Create an empty fake sysfs device directory, unique to `test`.
+    fn fake_device(test: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("linapi-test-{}-{}", process::id(), test));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(path.join("power")).unwrap();
+        path
+    }
+
+    #[test]
+    fn set_autosuspend_delay() {
+        let path = fake_device("set_autosuspend_delay");
+        fs::write(path.join("power/autosuspend_delay_ms"), "2000").unwrap();
+        let res = Power::new(&path).set_autosuspend_delay(Duration::from_millis(1500));
+        let written = fs::read_to_string(path.join("power/autosuspend_delay_ms"));
+        fs::remove_dir_all(&path).unwrap();
+        assert_eq!(res.unwrap(), Some(()));
+        assert_eq!(written.unwrap(), "1500");
+    }
+
+    #[test]
+    fn set_autosuspend_delay_missing() {
+        let path = fake_device("set_autosuspend_delay_missing");
+        let res = Power::new(&path).set_autosuspend_delay(Duration::from_millis(1500));
+        fs::remove_dir_all(&path).unwrap();
+        assert!(matches!(res, Err(Error::Io(e)) if e.kind() == io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn autosuspend_result() {
+        let err = |errno| Err(io::Error::from_raw_os_error(errno));
+        assert_eq!(Power::autosuspend_result(Ok(())).unwrap(), Some(()));
+        assert_eq!(Power::autosuspend_result(err(libc::EIO)).unwrap(), None);
+        assert!(Power::autosuspend_result(err(libc::EACCES)).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! This module provides ways to get information about hardware monitoring
+//! sensors, such as temperatures, fan speeds, and voltages.
+//!
+//! # Examples
+//!
+//! Print all temperature sensors
+//!
+//! ```rust,no_run
+//! # use linapi::system::devices::hwmon::Hwmon;
+//! for hwmon in Hwmon::get_connected().unwrap() {
+//!     for sensor in hwmon.temperatures().unwrap() {
+//!         println!(
+//!             "{} {}: {}°C",
+//!             hwmon.name().unwrap(),
+//!             sensor.label.unwrap_or_default(),
+//!             sensor.value
+//!         );
+//!     }
+//! }
+//! ```
+//!
+//! # Implementation
+//!
+//! This uses the sysfs interface, documented [here][1].
+//!
+//! [1]: https://www.kernel.org/doc/html/latest/hwmon/sysfs-interface.html
+use crate::util::{read_attr, read_attr_optional, SYSFS_PATH};
+use displaydoc::Display;
+use std::{
+    fs::DirEntry,
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Hwmon Error type
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// IO Failed
+    Io(#[from] io::Error),
+
+    /// The device or attribute was invalid
+    Invalid,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A single hardware monitoring sensor, see [`Hwmon::temperatures`],
+/// [`Hwmon::fans`], and [`Hwmon::voltages`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensor {
+    /// Sensor number, `N` in `tempN_input`.
+    pub index: u32,
+
+    /// Sensor label, if the driver provides one.
+    pub label: Option<String>,
+
+    /// Current sensor value.
+    ///
+    /// This is in degrees Celsius for temperatures, RPM for fans, and volts
+    /// for voltages.
+    pub value: f64,
+}
+
+/// A hardware monitoring device
+#[derive(Debug, Clone)]
+pub struct Hwmon {
+    /// Canonical, full, path to the device.
+    path: PathBuf,
+}
+
+// Public
+impl Hwmon {
+    /// Get connected hardware monitoring devices.
+    ///
+    /// The returned Vec is sorted by path.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Io`] for I/O errors
+    pub fn get_connected() -> Result<Vec<Self>> {
+        let path = Path::new(SYSFS_PATH).join("class/hwmon");
+        let mut devices = Vec::new();
+        if !path.exists() {
+            return Ok(devices);
+        }
+        for dev in path.read_dir()? {
+            let dev: DirEntry = dev?;
+            devices.push(Self {
+                path: dev.path().canonicalize()?,
+            });
+        }
+        devices.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+        Ok(devices)
+    }
+
+    /// Canonical path to the hwmon device.
+    ///
+    /// You normally shouldn't need this, but it could be useful if
+    /// you want to manually access information not exposed by this crate.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Name of the chip, such as `coretemp` or `k10temp`.
+    ///
+    /// This is not unique.
+    pub fn name(&self) -> Result<String> {
+        Ok(read_attr(&self.path, "name")?)
+    }
+
+    /// Temperature sensors, in degrees Celsius.
+    pub fn temperatures(&self) -> Result<Vec<Sensor>> {
+        // Reported in millidegrees Celsius
+        self.sensors("temp", 1000.0)
+    }
+
+    /// Fan sensors, in RPM.
+    pub fn fans(&self) -> Result<Vec<Sensor>> {
+        self.sensors("fan", 1.0)
+    }
+
+    /// Voltage sensors, in volts.
+    pub fn voltages(&self) -> Result<Vec<Sensor>> {
+        // Reported in millivolts
+        self.sensors("in", 1000.0)
+    }
+}
+
+// Private
+impl Hwmon {
+    /// Read all `{prefix}N_input` sensors, dividing their values by `scale`.
+    ///
+    /// Sensors that can't currently be read, such as disconnected fans, are
+    /// skipped. Other errors are returned.
+    fn sensors(&self, prefix: &str, scale: f64) -> Result<Vec<Sensor>> {
+        let mut sensors = Vec::new();
+        for entry in self.path.read_dir()? {
+            let entry: DirEntry = entry?;
+            let name = entry.file_name();
+            let index = match name
+                .to_str()
+                .and_then(|s| s.strip_prefix(prefix))
+                .and_then(|s| s.strip_suffix("_input"))
+                .and_then(|s| s.parse::<u32>().ok())
+            {
+                Some(i) => i,
+                None => continue,
+            };
+            let value = match read_attr(&self.path, &format!("{}{}_input", prefix, index)) {
+                Ok(s) => s.parse::<i64>().map_err(|_| Error::Invalid)?,
+                // Drivers return these for sensors that are currently unavailable
+                Err(e)
+                    if matches!(
+                        e.raw_os_error(),
+                        Some(libc::ENODATA | libc::EAGAIN | libc::ENXIO | libc::EOPNOTSUPP)
+                    ) =>
+                {
+                    continue
+                }
+                Err(e) => return Err(e.into()),
+            };
+            sensors.push(Sensor {
+                index,
+                label: read_attr_optional(&self.path, &format!("{}{}_label", prefix, index))?,
+                value: value as f64 / scale,
+            });
+        }
+        sensors.sort_unstable_by_key(|s| s.index);
+        Ok(sensors)
+    }
+}
@@ -0,0 +1,176 @@
+//! This module provides ways to get information about input devices, such as
+//! keyboards, mice, and touchpads
+//!
+//! # Examples
+//!
+//! Print all input devices and their event device
+//!
+//! ```rust,no_run
+//! # use linapi::system::devices::input::InputDevice;
+//! for dev in InputDevice::get_connected().unwrap() {
+//!     println!("{} {:?}", dev.name().unwrap(), dev.device_node().unwrap());
+//! }
+//! ```
+//!
+//! # Implementation
+//!
+//! This uses the sysfs interface, documented [here][1]. Event codes are
+//! defined in `linux/input-event-codes.h`.
+//!
+//! [1]: https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-class-input
+use crate::util::{read_attr, DEV_PATH, SYSFS_PATH};
+use displaydoc::Display;
+use std::{
+    collections::BTreeSet,
+    fs::DirEntry,
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Input Error type
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// IO Failed
+    Io(#[from] io::Error),
+
+    /// The device or attribute was invalid
+    Invalid,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Parse a capability bitmap into the set of bits that are set.
+///
+/// These are formatted as space separated hex words, of the kernels
+/// `unsigned long` size, with the most significant word first.
+fn parse_bitmap(s: &str) -> Result<BTreeSet<u16>> {
+    let bits = libc::c_ulong::BITS as u16;
+    let mut set = BTreeSet::new();
+    for (i, word) in s.split_whitespace().rev().enumerate() {
+        let word = u64::from_str_radix(word, 16).map_err(|_| Error::Invalid)?;
+        let base = u16::try_from(i)
+            .ok()
+            .and_then(|i| i.checked_mul(bits))
+            .ok_or(Error::Invalid)?;
+        for bit in 0..bits {
+            if word & (1 << bit) != 0 {
+                set.insert(base + bit);
+            }
+        }
+    }
+    Ok(set)
+}
+
+/// Input device capabilities, see [`InputDevice::capabilities`].
+///
+/// Each field is the set of supported event codes of that type, such as
+/// `EV_KEY` in `ev`, and `KEY_A` in `key`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Supported event types, `EV_*`.
+    pub ev: BTreeSet<u16>,
+
+    /// Supported keys and buttons, `KEY_*` and `BTN_*`.
+    pub key: BTreeSet<u16>,
+
+    /// Supported absolute axes, `ABS_*`.
+    pub abs: BTreeSet<u16>,
+
+    /// Supported relative axes, `REL_*`.
+    pub rel: BTreeSet<u16>,
+}
+
+/// An input device
+#[derive(Debug, Clone)]
+pub struct InputDevice {
+    /// Kernel name
+    name: String,
+
+    /// Canonical, full, path to the device.
+    path: PathBuf,
+}
+
+// Public
+impl InputDevice {
+    /// Get connected input devices.
+    ///
+    /// The returned Vec is sorted by kernel name.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Io`] for I/O errors
+    pub fn get_connected() -> Result<Vec<Self>> {
+        let path = Path::new(SYSFS_PATH).join("class/input");
+        let mut devices = Vec::new();
+        if !path.exists() {
+            return Ok(devices);
+        }
+        for dev in path.read_dir()? {
+            let dev: DirEntry = dev?;
+            let name = dev.file_name().into_string().map_err(|_| Error::Invalid)?;
+            // Skip the `eventN` and `mouseN` handlers, which are children of
+            // the `inputN` devices.
+            if !name.starts_with("input") {
+                continue;
+            }
+            devices.push(Self {
+                name,
+                path: dev.path().canonicalize()?,
+            });
+        }
+        devices.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        Ok(devices)
+    }
+
+    /// Kernel name for this device, such as `input0`.
+    pub fn kernel_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Canonical path to the input device.
+    ///
+    /// You normally shouldn't need this, but it could be useful if
+    /// you want to manually access information not exposed by this crate.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Descriptive name of the device, such as `AT Translated Set 2 keyboard`.
+    pub fn name(&self) -> Result<String> {
+        Ok(read_attr(&self.path, "name")?)
+    }
+
+    /// Get the event types and codes the device supports.
+    ///
+    /// See [`Capabilities`] for more details.
+    pub fn capabilities(&self) -> Result<Capabilities> {
+        let path = self.path.join("capabilities");
+        let get = |attr| parse_bitmap(&read_attr(&path, attr)?);
+        Ok(Capabilities {
+            ev: get("ev")?,
+            key: get("key")?,
+            abs: get("abs")?,
+            rel: get("rel")?,
+        })
+    }
+
+    /// Path to the evdev device file for this device, usually
+    /// `/dev/input/eventN`.
+    ///
+    /// Returns [`None`] if the device has no evdev handler.
+    pub fn device_node(&self) -> Result<Option<PathBuf>> {
+        for entry in self.path.read_dir()? {
+            let entry: DirEntry = entry?;
+            let name = entry.file_name();
+            let name = match name.to_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            if name.starts_with("event") {
+                return Ok(Some(Path::new(DEV_PATH).join("input").join(name)));
+            }
+        }
+        Ok(None)
+    }
+}
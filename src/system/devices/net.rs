@@ -0,0 +1,239 @@
+//! This module provides ways to get information about network interfaces
+//!
+//! # Examples
+//!
+//! Print the state of all network interfaces
+//!
+//! ```rust,no_run
+//! # use linapi::system::devices::net::NetDevice;
+//! for dev in NetDevice::get_connected().unwrap() {
+//!     println!(
+//!         "{} {} {:?}",
+//!         dev.name(),
+//!         dev.mac_address().unwrap(),
+//!         dev.operstate().unwrap()
+//!     );
+//! }
+//! ```
+//!
+//! # Implementation
+//!
+//! This uses the sysfs interface, documented [here][1].
+//!
+//! [1]: https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-class-net
+use crate::util::{attr_error, read_attr, read_attr_parsed, SYSFS_PATH};
+use displaydoc::Display;
+use std::{
+    fs::DirEntry,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use thiserror::Error;
+
+/// Net Error type
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// IO Failed
+    Io(#[from] io::Error),
+
+    /// The device or attribute was invalid
+    Invalid,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Operational state of an interface, see [`NetDevice::operstate`].
+///
+/// See RFC 2863 for details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OperState {
+    /// Interface is up and can pass packets
+    Up,
+
+    /// Interface is down
+    Down,
+
+    /// Interface is waiting for an external event, such as authentication
+    Dormant,
+
+    /// Interface is down because a device it's stacked on is down
+    LowerLayerDown,
+
+    /// Interface is missing a component, usually hardware
+    NotPresent,
+
+    /// Interface is in test mode
+    Testing,
+
+    /// Unknown, such as `unknown` for drivers that don't report state
+    Unknown(String),
+}
+
+/// Network interface statistics, see [`NetDevice::statistics`].
+///
+/// See the [linux kernel docs][1] for details on these.
+///
+/// [1]: https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-class-net-statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetStats {
+    /// Number of bytes received
+    pub rx_bytes: u64,
+
+    /// Number of packets received
+    pub rx_packets: u64,
+
+    /// Number of receive errors
+    pub rx_errors: u64,
+
+    /// Number of received packets dropped
+    pub rx_dropped: u64,
+
+    /// Number of bytes transmitted
+    pub tx_bytes: u64,
+
+    /// Number of packets transmitted
+    pub tx_packets: u64,
+
+    /// Number of transmit errors
+    pub tx_errors: u64,
+
+    /// Number of packets dropped before transmitting
+    pub tx_dropped: u64,
+
+    /// Number of multicast packets received
+    pub multicast: u64,
+
+    /// Number of collisions while transmitting
+    pub collisions: u64,
+}
+
+/// A network interface
+#[derive(Debug, Clone)]
+pub struct NetDevice {
+    /// Interface name
+    name: String,
+
+    /// Canonical, full, path to the device.
+    path: PathBuf,
+}
+
+// Public
+impl NetDevice {
+    /// Get network interfaces.
+    ///
+    /// The returned Vec is sorted by interface name.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Io`] for I/O errors
+    pub fn get_connected() -> Result<Vec<Self>> {
+        let path = Path::new(SYSFS_PATH).join("class/net");
+        let mut devices = Vec::new();
+        if !path.exists() {
+            return Ok(devices);
+        }
+        for dev in path.read_dir()? {
+            let dev: DirEntry = dev?;
+            // `bonding_masters` is a file, not an interface.
+            if !dev.path().is_dir() {
+                continue;
+            }
+            devices.push(Self {
+                name: dev.file_name().into_string().map_err(|_| Error::Invalid)?,
+                path: dev.path().canonicalize()?,
+            });
+        }
+        devices.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        Ok(devices)
+    }
+
+    /// Interface name, such as `eth0` or `lo`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Canonical path to the interface.
+    ///
+    /// You normally shouldn't need this, but it could be useful if
+    /// you want to manually access information not exposed by this crate.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Hardware address of the interface, such as `00:11:22:33:44:55`.
+    pub fn mac_address(&self) -> Result<String> {
+        Ok(read_attr(&self.path, "address")?)
+    }
+
+    /// Maximum transmission unit, in bytes.
+    pub fn mtu(&self) -> Result<u32> {
+        read_attr_parsed(&self.path, "mtu").map_err(|e| attr_error(e, Error::Invalid))
+    }
+
+    /// Operational state of the interface.
+    pub fn operstate(&self) -> Result<OperState> {
+        Ok(match &*read_attr(&self.path, "operstate")? {
+            "up" => OperState::Up,
+            "down" => OperState::Down,
+            "dormant" => OperState::Dormant,
+            "lowerlayerdown" => OperState::LowerLayerDown,
+            "notpresent" => OperState::NotPresent,
+            "testing" => OperState::Testing,
+            s => OperState::Unknown(s.into()),
+        })
+    }
+
+    /// Whether the interface has a physical link.
+    ///
+    /// Returns [`None`] if the interface is administratively down.
+    pub fn carrier(&self) -> Result<Option<bool>> {
+        Ok(self.read_invalid::<u8>("carrier")?.map(|c| c != 0))
+    }
+
+    /// Link speed, in Mbps.
+    ///
+    /// Returns [`None`] if the link is down, or if the interface doesn't
+    /// have a speed, such as for virtual interfaces.
+    pub fn speed(&self) -> Result<Option<u32>> {
+        // Unknown speed is reported as `-1`
+        Ok(self
+            .read_invalid::<i64>("speed")?
+            .and_then(|s| s.try_into().ok()))
+    }
+
+    /// Get traffic statistics for the interface.
+    ///
+    /// See [`NetStats`] for more details.
+    pub fn statistics(&self) -> Result<NetStats> {
+        let path = self.path.join("statistics");
+        let get = |attr| read_attr_parsed(&path, attr).map_err(|e| attr_error(e, Error::Invalid));
+        Ok(NetStats {
+            rx_bytes: get("rx_bytes")?,
+            rx_packets: get("rx_packets")?,
+            rx_errors: get("rx_errors")?,
+            rx_dropped: get("rx_dropped")?,
+            tx_bytes: get("tx_bytes")?,
+            tx_packets: get("tx_packets")?,
+            tx_errors: get("tx_errors")?,
+            tx_dropped: get("tx_dropped")?,
+            multicast: get("multicast")?,
+            collisions: get("collisions")?,
+        })
+    }
+}
+
+// Private
+impl NetDevice {
+    /// Read and parse `attr`.
+    ///
+    /// Returns [`None`] if the kernel returns `EINVAL`, which it does for
+    /// attributes that don't currently apply to the interface.
+    fn read_invalid<T: FromStr>(&self, attr: &str) -> Result<Option<T>> {
+        match read_attr_parsed(&self.path, attr) {
+            Ok(v) => Ok(Some(v)),
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => Ok(None),
+            Err(e) => Err(attr_error(e, Error::Invalid)),
+        }
+    }
+}
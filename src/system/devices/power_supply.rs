@@ -0,0 +1,210 @@
+//! This module provides ways to get information about power supplies, such
+//! as batteries and AC adapters.
+//!
+//! # Examples
+//!
+//! Print the charge of all batteries
+//!
+//! ```rust,no_run
+//! # use linapi::system::devices::power_supply::{PowerSupply, SupplyType};
+//! for supply in PowerSupply::get_connected().unwrap() {
+//!     if supply.kind().unwrap() == SupplyType::Battery {
+//!         println!(
+//!             "{}: {:?}% {:?}",
+//!             supply.name(),
+//!             supply.capacity_percent().unwrap(),
+//!             supply.status().unwrap()
+//!         );
+//!     }
+//! }
+//! ```
+//!
+//! # Implementation
+//!
+//! This uses the sysfs interface, documented [here][1].
+//!
+//! [1]: https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-class-power
+use crate::util::{read_attr, read_attr_optional, SYSFS_PATH};
+use displaydoc::Display;
+use std::{
+    fs::DirEntry,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use thiserror::Error;
+
+/// Power supply Error type
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// IO Failed
+    Io(#[from] io::Error),
+
+    /// The device or attribute was invalid
+    Invalid,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Type of power supply, see [`PowerSupply::kind`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SupplyType {
+    /// A battery
+    Battery,
+
+    /// Uninterruptible power supply
+    Ups,
+
+    /// AC power, such as a laptop charger
+    Mains,
+
+    /// USB power
+    Usb,
+
+    /// Wireless charging
+    Wireless,
+
+    /// Unknown
+    Unknown(String),
+}
+
+/// Battery charging status, see [`PowerSupply::status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// Charging
+    Charging,
+
+    /// Discharging
+    Discharging,
+
+    /// Not discharging, but not charging either.
+    NotCharging,
+
+    /// Fully charged
+    Full,
+
+    /// Unknown
+    Unknown(String),
+}
+
+/// A power supply
+#[derive(Debug, Clone)]
+pub struct PowerSupply {
+    /// Kernel name
+    name: String,
+
+    /// Canonical, full, path to the device.
+    path: PathBuf,
+}
+
+// Public
+impl PowerSupply {
+    /// Get connected power supplies.
+    ///
+    /// The returned Vec is sorted by kernel name.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Io`] for I/O errors
+    pub fn get_connected() -> Result<Vec<Self>> {
+        let path = Path::new(SYSFS_PATH).join("class/power_supply");
+        let mut devices = Vec::new();
+        if !path.exists() {
+            return Ok(devices);
+        }
+        for dev in path.read_dir()? {
+            let dev: DirEntry = dev?;
+            devices.push(Self {
+                name: dev.file_name().into_string().map_err(|_| Error::Invalid)?,
+                path: dev.path().canonicalize()?,
+            });
+        }
+        devices.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        Ok(devices)
+    }
+
+    /// Kernel name for this power supply, such as `BAT0` or `AC`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Canonical path to the power supply.
+    ///
+    /// You normally shouldn't need this, but it could be useful if
+    /// you want to manually access information not exposed by this crate.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Type of power supply.
+    pub fn kind(&self) -> Result<SupplyType> {
+        Ok(match &*read_attr(&self.path, "type")? {
+            "Battery" => SupplyType::Battery,
+            "UPS" => SupplyType::Ups,
+            "Mains" => SupplyType::Mains,
+            "USB" => SupplyType::Usb,
+            "Wireless" => SupplyType::Wireless,
+            s => SupplyType::Unknown(s.into()),
+        })
+    }
+
+    /// Whether the power supply is present, such as whether a battery is
+    /// inserted.
+    pub fn present(&self) -> Result<Option<bool>> {
+        Ok(self.read_optional::<u8>("present")?.map(|p| p != 0))
+    }
+
+    /// Whether the power supply is online, such as whether an AC adapter is
+    /// plugged in.
+    pub fn online(&self) -> Result<Option<bool>> {
+        Ok(self.read_optional::<u8>("online")?.map(|p| p != 0))
+    }
+
+    /// Battery charge, in percent.
+    pub fn capacity_percent(&self) -> Result<Option<u8>> {
+        self.read_optional("capacity")
+    }
+
+    /// Battery charging status.
+    pub fn status(&self) -> Result<Option<Status>> {
+        let s = match read_attr_optional(&self.path, "status")? {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        Ok(Some(match &*s {
+            "Charging" => Status::Charging,
+            "Discharging" => Status::Discharging,
+            "Not charging" => Status::NotCharging,
+            "Full" => Status::Full,
+            _ => Status::Unknown(s),
+        }))
+    }
+
+    /// Current battery energy, in µWh.
+    pub fn energy_now(&self) -> Result<Option<u64>> {
+        self.read_optional("energy_now")
+    }
+
+    /// Battery energy when full, in µWh.
+    pub fn energy_full(&self) -> Result<Option<u64>> {
+        self.read_optional("energy_full")
+    }
+
+    /// Current voltage, in µV.
+    pub fn voltage_now(&self) -> Result<Option<u64>> {
+        self.read_optional("voltage_now")
+    }
+}
+
+// Private
+impl PowerSupply {
+    /// Read and parse `attr`, if it exists.
+    ///
+    /// Power supplies only have the attributes their driver supports.
+    fn read_optional<T: FromStr>(&self, attr: &str) -> Result<Option<T>> {
+        match read_attr_optional(&self.path, attr)? {
+            Some(s) => s.parse().map(Some).map_err(|_| Error::Invalid),
+            None => Ok(None),
+        }
+    }
+}
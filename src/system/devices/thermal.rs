@@ -0,0 +1,254 @@
+//! This module provides ways to get information about thermal zones and
+//! cooling devices
+//!
+//! # Examples
+//!
+//! Print the temperature of all thermal zones
+//!
+//! ```rust,no_run
+//! # use linapi::system::devices::thermal::ThermalZone;
+//! for zone in ThermalZone::get_connected().unwrap() {
+//!     println!(
+//!         "{}: {}°C",
+//!         zone.zone_type().unwrap(),
+//!         zone.temperature().unwrap()
+//!     );
+//! }
+//! ```
+//!
+//! # Implementation
+//!
+//! This uses the sysfs interface, documented [here][1].
+//!
+//! [1]: https://www.kernel.org/doc/html/latest/driver-api/thermal/sysfs-api.html
+use crate::util::{attr_error, read_attr, read_attr_optional, read_attr_parsed, SYSFS_PATH};
+use displaydoc::Display;
+use std::{
+    fs,
+    fs::DirEntry,
+    io,
+    io::prelude::*,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Thermal Error type
+#[derive(Debug, Display, Error)]
+pub enum Error {
+    /// IO Failed
+    Io(#[from] io::Error),
+
+    /// Invalid argument: {0}
+    InvalidArg(&'static str),
+
+    /// The device or attribute was invalid
+    Invalid,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Get all devices in `/sys/class/thermal` whose name starts with `prefix`,
+/// sorted by name.
+fn get_devices(prefix: &str) -> Result<Vec<(String, PathBuf)>> {
+    let path = Path::new(SYSFS_PATH).join("class/thermal");
+    let mut devices = Vec::new();
+    if !path.exists() {
+        return Ok(devices);
+    }
+    for dev in path.read_dir()? {
+        let dev: DirEntry = dev?;
+        let name = dev.file_name().into_string().map_err(|_| Error::Invalid)?;
+        if !name.starts_with(prefix) {
+            continue;
+        }
+        devices.push((name, dev.path().canonicalize()?));
+    }
+    devices.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    Ok(devices)
+}
+
+/// Type of trip point, see [`TripPoint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TripType {
+    /// Active cooling, such as fans, is turned on
+    Active,
+
+    /// Passive cooling, such as throttling, is used
+    Passive,
+
+    /// The system is getting too hot
+    Hot,
+
+    /// The system will shut down
+    Critical,
+
+    /// Unknown
+    Unknown(String),
+}
+
+/// A thermal zone trip point, see [`ThermalZone::trip_points`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TripPoint {
+    /// Trip point number, `N` in `trip_point_N_temp`.
+    pub index: u32,
+
+    /// Type of trip point
+    pub kind: TripType,
+
+    /// Temperature the trip point triggers at, in degrees Celsius.
+    pub temperature: f64,
+}
+
+/// A thermal zone
+#[derive(Debug, Clone)]
+pub struct ThermalZone {
+    /// Kernel name
+    name: String,
+
+    /// Canonical, full, path to the device.
+    path: PathBuf,
+}
+
+// Public
+impl ThermalZone {
+    /// Get thermal zones.
+    ///
+    /// The returned Vec is sorted by kernel name.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Io`] for I/O errors
+    pub fn get_connected() -> Result<Vec<Self>> {
+        Ok(get_devices("thermal_zone")?
+            .into_iter()
+            .map(|(name, path)| Self { name, path })
+            .collect())
+    }
+
+    /// Kernel name for this thermal zone, such as `thermal_zone0`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Canonical path to the thermal zone.
+    ///
+    /// You normally shouldn't need this, but it could be useful if
+    /// you want to manually access information not exposed by this crate.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Type of the thermal zone, such as `x86_pkg_temp` or `acpitz`.
+    pub fn zone_type(&self) -> Result<String> {
+        Ok(read_attr(&self.path, "type")?)
+    }
+
+    /// Current temperature, in degrees Celsius.
+    pub fn temperature(&self) -> Result<f64> {
+        // Reported in millidegrees Celsius
+        let t: i64 =
+            read_attr_parsed(&self.path, "temp").map_err(|e| attr_error(e, Error::Invalid))?;
+        Ok(t as f64 / 1000.0)
+    }
+
+    /// Trip points for this thermal zone, in order.
+    pub fn trip_points(&self) -> Result<Vec<TripPoint>> {
+        let mut trips = Vec::new();
+        for index in 0.. {
+            // Trip points are numbered contiguously
+            let temp = match read_attr_optional(&self.path, &format!("trip_point_{}_temp", index))?
+            {
+                Some(t) => t.parse::<i64>().map_err(|_| Error::Invalid)?,
+                None => break,
+            };
+            let kind = match &*read_attr(&self.path, &format!("trip_point_{}_type", index))? {
+                "active" => TripType::Active,
+                "passive" => TripType::Passive,
+                "hot" => TripType::Hot,
+                "critical" => TripType::Critical,
+                s => TripType::Unknown(s.into()),
+            };
+            trips.push(TripPoint {
+                index,
+                kind,
+                temperature: temp as f64 / 1000.0,
+            });
+        }
+        Ok(trips)
+    }
+}
+
+/// A cooling device, such as a fan or processor throttling
+#[derive(Debug, Clone)]
+pub struct CoolingDevice {
+    /// Kernel name
+    name: String,
+
+    /// Canonical, full, path to the device.
+    path: PathBuf,
+}
+
+// Public
+impl CoolingDevice {
+    /// Get cooling devices.
+    ///
+    /// The returned Vec is sorted by kernel name.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Io`] for I/O errors
+    pub fn get_connected() -> Result<Vec<Self>> {
+        Ok(get_devices("cooling_device")?
+            .into_iter()
+            .map(|(name, path)| Self { name, path })
+            .collect())
+    }
+
+    /// Kernel name for this cooling device, such as `cooling_device0`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Canonical path to the cooling device.
+    ///
+    /// You normally shouldn't need this, but it could be useful if
+    /// you want to manually access information not exposed by this crate.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Type of the cooling device, such as `Processor` or `Fan`.
+    pub fn cooling_type(&self) -> Result<String> {
+        Ok(read_attr(&self.path, "type")?)
+    }
+
+    /// Current cooling state, from `0` to [`CoolingDevice::max_state`].
+    ///
+    /// Higher states mean more cooling.
+    pub fn cur_state(&self) -> Result<u64> {
+        read_attr_parsed(&self.path, "cur_state").map_err(|e| attr_error(e, Error::Invalid))
+    }
+
+    /// Maximum cooling state.
+    pub fn max_state(&self) -> Result<u64> {
+        read_attr_parsed(&self.path, "max_state").map_err(|e| attr_error(e, Error::Invalid))
+    }
+
+    /// Set the current cooling state.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArg`] if `state` is larger than
+    ///   [`CoolingDevice::max_state`]
+    /// - If I/O does
+    pub fn set_cur_state(&mut self, state: u64) -> Result<()> {
+        if state > self.max_state()? {
+            return Err(Error::InvalidArg("State was larger than the maximum"));
+        }
+        let mut f = fs::OpenOptions::new()
+            .write(true)
+            .open(self.path.join("cur_state"))?;
+        write!(f, "{}", state)?;
+        Ok(())
+    }
+}
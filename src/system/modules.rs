@@ -24,7 +24,7 @@
 //! println!(
 //!     "Loaded module {}. my_param={}",
 //!     loaded.name(),
-//!     std::str::from_utf8(&loaded.parameters()["my_param"]).unwrap()
+//!     std::str::from_utf8(&loaded.parameters().unwrap()["my_param"]).unwrap()
 //! );
 //! ```
 //!
@@ -36,28 +36,40 @@
 //! [1]: https://www.kernel.org/doc/Documentation/ABI/stable/sysfs-module
 //! [2]: https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-module
 use crate::{
-    error::{text::*, ModuleError, ModuleErrorKind, ModuleError_},
+    error::{text::*, ModuleError, ModuleErrorKind, ModuleError_, ParamError},
     extensions::FileExt,
     system::{UEvent, UEventAction},
-    util::{read_uevent, write_uevent, MODULE_PATH, SYSFS_PATH},
+    util::{
+        kernel_release, read_attr, read_attr_optional, read_attr_parsed, read_uevent, write_uevent,
+        FIRMWARE_PATH, MODULE_PATH, PROC_PATH, SYSFS_PATH,
+    },
 };
+use bitflags::bitflags;
 #[cfg(feature = "gz")]
-use flate2::bufread::GzDecoder;
-use nix::{
-    kmod::{delete_module, finit_module, init_module, DeleteModuleFlags, ModuleInitFlags},
-    sys::utsname::uname,
-};
+use flate2::bufread::{GzDecoder, ZlibDecoder};
+#[cfg(feature = "lz4")]
+use lz4_flex::frame::FrameDecoder as Lz4Decoder;
+use nix::kmod::{delete_module, finit_module, init_module, DeleteModuleFlags, ModuleInitFlags};
 use std::{
+    borrow::Cow,
     collections::HashMap,
     ffi::CString,
+    fmt::Display,
     fs,
     fs::DirEntry,
     io,
     io::{prelude::*, BufRead},
+    os::unix::fs::FileExt as _,
     path::{Path, PathBuf},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
 };
 use walkdir::WalkDir;
-use xmas_elf::ElfFile;
+use xmas_elf::{
+    header::{parse_header, Class, Data},
+    sections::SHF_COMPRESSED,
+};
 #[cfg(feature = "xz")]
 use xz2::bufread::XzDecoder;
 #[cfg(feature = "zst")]
@@ -65,34 +77,303 @@ use zstd::stream::read::Decoder as ZstDecoder;
 
 const SIGNATURE_MAGIC: &[u8] = b"~Module signature appended~\n";
 
+/// Size of `struct module_signature`, from `linux/module_signature.h`.
+///
+/// This comes right before [`SIGNATURE_MAGIC`], and ends with the big endian
+/// signature length.
+const SIGNATURE_INFO_LEN: usize = 12;
+
+/// ELF section compression types, from `elf(5)`.
+#[cfg(feature = "gz")]
+const ELFCOMPRESS_ZLIB: u32 = 1;
+#[cfg(feature = "zst")]
+const ELFCOMPRESS_ZSTD: u32 = 2;
+
+/// ELF section type for notes, from `elf.h`.
+const SHT_NOTE: u64 = 7;
+
+/// ELF note type for the GNU build-id, from `elf.h`.
+const NT_GNU_BUILD_ID: u32 = 3;
+
 pub type Result<T, E = Box<dyn std::error::Error + Send + Sync>> = std::result::Result<T, E>;
 
-/// Helper to read the `attribute` at `path`. Trims it.
-fn read_attribute<P: AsRef<Path>>(base: P, attribute: &'static str) -> Result<String, io::Error> {
-    fs::read_to_string(base.as_ref().join(attribute)).map(|s| s.trim().to_owned())
+/// Split a module image `img` into its body and signature, if any.
+///
+/// # Implementation
+///
+/// This follows `mod_verify_sig` in `kernel/module_signing.c`.
+fn split_signature(img: &[u8]) -> Result<(&[u8], Option<&[u8]>)> {
+    let img = match img.strip_suffix(SIGNATURE_MAGIC) {
+        Some(img) => img,
+        None => return Ok((img, None)),
+    };
+    let invalid = || ModuleError::InvalidModule(SIGNATURE.into());
+    let info_start = img
+        .len()
+        .checked_sub(SIGNATURE_INFO_LEN)
+        .ok_or_else(invalid)?;
+    let (img, info) = img.split_at(info_start);
+    let sig_len = u32::from_be_bytes(info[8..].try_into().unwrap()) as usize;
+    let sig_start = img.len().checked_sub(sig_len).ok_or_else(invalid)?;
+    let (body, sig) = img.split_at(sig_start);
+    Ok((body, Some(sig)))
 }
 
-/// Kernel modules can be "tainted", which serve as a marker for debugging
-/// purposes.
-#[derive(Debug, Clone, Copy)]
-pub enum Taint {
-    /// Not tainted
-    Clean,
+bitflags! {
+    /// Kernel modules can be "tainted", which serve as a marker for debugging
+    /// purposes.
+    ///
+    /// An empty set means the module is not tainted.
+    ///
+    /// These use the same bits as the kernels `/proc/sys/kernel/tainted`.
+    /// See the [linux kernel docs][1] for details.
+    ///
+    /// [1]: https://www.kernel.org/doc/html/latest/admin-guide/tainted-kernels.html
+    pub struct TaintFlags: u32 {
+        /// Proprietary Module. `P`
+        const PROPRIETARY = 1 << 0;
 
-    /// Proprietary Module.
-    Proprietary,
+        /// Module was force loaded. `F`
+        const FORCED = 1 << 1;
+
+        /// Unstable Staging Module. `C`
+        const STAGING = 1 << 10;
+
+        /// Out of tree, third party Module. `O`
+        const OUT_OF_TREE = 1 << 12;
+
+        /// Unsigned Module. `E`
+        const UNSIGNED = 1 << 13;
+
+        /// Live patch Module. `K`
+        const LIVEPATCH = 1 << 15;
+
+        /// Auxiliary taint, defined by and for distros. `X`
+        const AUX = 1 << 16;
+
+        /// Module was built with a structure randomization plugin. `T`
+        const RANDSTRUCT = 1 << 17;
+
+        /// Test Module. `N`
+        const TEST = 1 << 18;
+
+        /// One or more taint flags unknown to this library.
+        ///
+        /// This is not a real kernel taint flag.
+        const UNKNOWN = 1 << 31;
+    }
+}
+
+/// Parse the taint flags in `s`, which are one character each.
+fn parse_taint(s: &str) -> TaintFlags {
+    let mut taint = TaintFlags::empty();
+    for c in s.chars() {
+        taint |= match c {
+            'P' => TaintFlags::PROPRIETARY,
+            'F' => TaintFlags::FORCED,
+            'C' => TaintFlags::STAGING,
+            'O' => TaintFlags::OUT_OF_TREE,
+            'E' => TaintFlags::UNSIGNED,
+            'K' => TaintFlags::LIVEPATCH,
+            'X' => TaintFlags::AUX,
+            'T' => TaintFlags::RANDSTRUCT,
+            'N' => TaintFlags::TEST,
+            _ => TaintFlags::UNKNOWN,
+        };
+    }
+    taint
+}
+
+/// Decompress the ELF section `raw`, which has `SHF_COMPRESSED` set, from
+/// an ELF file of `class` and `data` encoding.
+///
+/// These start with a `Elf32_Chdr` or `Elf64_Chdr` header, see `elf(5)`.
+fn decompress_section(class: Class, data: Data, raw: &[u8]) -> Result<Vec<u8>> {
+    let header_len = match class {
+        Class::ThirtyTwo => 12,
+        Class::SixtyFour => 24,
+        _ => return Err(ModuleError::InvalidModule(MODINFO.into()).into()),
+    };
+    if raw.len() < header_len {
+        return Err(ModuleError::InvalidModule(MODINFO.into()).into());
+    }
+    let type_ = [raw[0], raw[1], raw[2], raw[3]];
+    let type_ = match data {
+        Data::BigEndian => u32::from_be_bytes(type_),
+        _ => u32::from_le_bytes(type_),
+    };
+    #[cfg(any(feature = "gz", feature = "zst"))]
+    let (mut v, data) = (Vec::new(), &raw[header_len..]);
+    match type_ {
+        #[cfg(feature = "gz")]
+        ELFCOMPRESS_ZLIB => {
+            ZlibDecoder::new(data)
+                .read_to_end(&mut v)
+                .map_err(|e| ModuleError::InvalidModule(e.to_string()))?;
+            Ok(v)
+        }
+        #[cfg(feature = "zst")]
+        ELFCOMPRESS_ZSTD => {
+            ZstDecoder::new(data)
+                .map_err(|_| ModuleError::InvalidModule(COMPRESSION.into()))?
+                .read_to_end(&mut v)
+                .map_err(|e| ModuleError::InvalidModule(e.to_string()))?;
+            Ok(v)
+        }
+        _ => Err(ModuleError::InvalidModule(COMPRESSION.into()).into()),
+    }
+}
+
+/// Find the `NT_GNU_BUILD_ID` note in the note sections `notes`, of `data`
+/// encoding, hex encoded.
+fn read_build_id(data: Data, notes: &[Cow<[u8]>]) -> Option<String> {
+    let u32_at = |b: &[u8], i: usize| -> Option<u32> {
+        let b = b.get(i..i + 4)?.try_into().ok()?;
+        Some(match data {
+            Data::BigEndian => u32::from_be_bytes(b),
+            _ => u32::from_le_bytes(b),
+        })
+    };
+    let align = |n: usize| (n + 3) & !3;
+    for data in notes {
+        // Notes are a header of name size, description size, and type,
+        // followed by the name and description, each padded to 4 bytes.
+        let mut data = &**data;
+        while data.len() >= 12 {
+            let name_len = u32_at(data, 0)? as usize;
+            let desc_len = u32_at(data, 4)? as usize;
+            let type_ = u32_at(data, 8)?;
+            let name = data.get(12..12 + name_len)?;
+            let desc_start = 12 + align(name_len);
+            let desc = data.get(desc_start..desc_start + desc_len)?;
+            if type_ == NT_GNU_BUILD_ID && name == b"GNU\0" {
+                return Some(desc.iter().map(|b| format!("{:02x}", b)).collect());
+            }
+            data = data.get(desc_start + align(desc_len)..).unwrap_or_default();
+        }
+    }
+    None
+}
+
+/// The parts of a module image that [`ModInfo`] is parsed from.
+struct InfoSections<'a> {
+    /// ELF data encoding
+    data: Data,
 
-    /// Out of tree, third party Module.
-    OutOfTree,
+    /// Decompressed `.modinfo` section
+    modinfo: Cow<'a, [u8]>,
 
-    /// Module was force loaded.
-    Forced,
+    /// Every `SHT_NOTE` section
+    notes: Vec<Cow<'a, [u8]>>,
+}
+
+impl<'a> InfoSections<'a> {
+    /// Get the sections from the module image `img`.
+    fn from_bytes(img: &'a [u8]) -> Result<Self> {
+        Self::parse(img.len() as u64, |offset, len| {
+            // `parse` already checked these are in bounds
+            Ok(Cow::Borrowed(&img[offset as usize..][..len as usize]))
+        })
+    }
 
-    /// Unstable Staging Module.
-    Staging,
+    /// Read the sections from the uncompressed module `file`.
+    ///
+    /// Only the ELF header, section headers, and needed sections are read,
+    /// as modules can be very large.
+    ///
+    /// # Implementation
+    ///
+    /// This uses `pread` instead of a memory map, because the file being
+    /// truncated or changed while mapped could crash with `SIGBUS`, or change
+    /// data we've already checked.
+    fn from_file(file: &fs::File) -> Result<InfoSections<'static>> {
+        InfoSections::parse(file.metadata()?.len(), |offset, len| {
+            let mut buf = vec![0; len.try_into()?];
+            file.read_exact_at(&mut buf, offset)?;
+            Ok(Cow::Owned(buf))
+        })
+    }
 
-    /// Unsigned Module.
-    Unsigned,
+    /// Parse the sections from an ELF image `img_len` bytes long, using
+    /// `read` to get `len` bytes at `offset`.
+    ///
+    /// Every offset and length is checked against `img_len` before calling
+    /// `read`, so truncated or corrupt images are an error instead of a panic.
+    fn parse<F>(img_len: u64, read: F) -> Result<Self>
+    where
+        F: Fn(u64, u64) -> Result<Cow<'a, [u8]>>,
+    {
+        let invalid = || ModuleError::InvalidModule(MODINFO.into());
+        let read_at = |offset: u64, len: u64| -> Result<Cow<'a, [u8]>> {
+            if offset.checked_add(len).ok_or_else(invalid)? > img_len {
+                return Err(invalid().into());
+            }
+            read(offset, len)
+        };
+        // Large enough for either ELF header.
+        // Copied so it's aligned as `parse_header` requires.
+        let header = read_at(0, img_len.min(64))?.into_owned();
+        let header = parse_header(&header).map_err(|e| ModuleError::InvalidModule(e.into()))?;
+        let (class, data) = (header.pt1.class(), header.pt1.data());
+        // Offsets and lengths of the section header fields we need,
+        // `sh_name`, `sh_type`, `sh_flags`, `sh_offset`, and `sh_size`.
+        let (fields, min_len) = match class {
+            Class::ThirtyTwo => ([(0, 4), (4, 4), (8, 4), (16, 4), (20, 4)], 40),
+            _ => ([(0, 4), (4, 4), (8, 8), (24, 8), (32, 8)], 64),
+        };
+        let entry_len = usize::from(header.pt2.sh_entry_size());
+        if entry_len < min_len {
+            return Err(invalid().into());
+        }
+        let table = read_at(
+            header.pt2.sh_offset(),
+            entry_len as u64 * u64::from(header.pt2.sh_count()),
+        )?;
+        let uint = |b: &[u8]| {
+            let mut buf = [0; 8];
+            match data {
+                Data::BigEndian => {
+                    buf[8 - b.len()..].copy_from_slice(b);
+                    u64::from_be_bytes(buf)
+                }
+                _ => {
+                    buf[..b.len()].copy_from_slice(b);
+                    u64::from_le_bytes(buf)
+                }
+            }
+        };
+        let sections: Vec<[u64; 5]> = table
+            .chunks_exact(entry_len)
+            .map(|sh| fields.map(|(i, len)| uint(&sh[i..i + len])))
+            .collect();
+        let [_, _, _, offset, size] = *sections
+            .get(usize::from(header.pt2.sh_str_index()))
+            .ok_or_else(invalid)?;
+        let names = read_at(offset, size)?;
+        //
+        let mut modinfo = None;
+        let mut notes = Vec::new();
+        for [name, type_, flags, offset, size] in sections {
+            let name = names
+                .get(name as usize..)
+                .and_then(|s| s.split(|b| *b == 0).next());
+            if name == Some(b".modinfo") && modinfo.is_none() {
+                let raw = read_at(offset, size)?;
+                modinfo = Some(if flags & SHF_COMPRESSED != 0 {
+                    Cow::Owned(decompress_section(class, data, &raw)?)
+                } else {
+                    raw
+                });
+            } else if type_ == SHT_NOTE {
+                notes.push(read_at(offset, size)?);
+            }
+        }
+        Ok(Self {
+            data,
+            modinfo: modinfo.ok_or_else(invalid)?,
+            notes,
+        })
+    }
 }
 
 /// Module type
@@ -154,23 +435,87 @@ impl LoadedModule {
         Self::from_dir(&Path::new(SYSFS_PATH).join("module").join(name))
     }
 
+    /// Get an already loaded module from it's sysfs directory,
+    /// `/sys/module/<name>`.
+    ///
+    /// This is useful if you already have the path, such as from a uevent.
+    ///
+    /// # Errors
+    ///
+    /// - If no such module exists
+    /// - If the module is invalid in some way
+    pub fn from_sys_path(path: &Path) -> Result<Self> {
+        Self::from_dir(path)
+    }
+
     /// Get currently loaded dynamic kernel modules.
     ///
+    /// Modules that couldn't be read, such as those being unloaded, are
+    /// skipped. See [`LoadedModule::get_loaded_lossy`] to handle them yourself.
+    ///
     /// # Errors
     ///
     /// - I/O
-    /// - If any modules couldn't be read
     pub fn get_loaded() -> Result<Vec<Self>> {
+        Ok(Self::get_loaded_lossy()?
+            .into_iter()
+            .filter_map(|m| m.ok())
+            .collect())
+    }
+
+    /// Get currently loaded dynamic kernel modules, and any errors reading
+    /// them.
+    ///
+    /// There is one entry for each module, so you can decide how to handle
+    /// errors for each one.
+    ///
+    /// # Errors
+    ///
+    /// - I/O
+    pub fn get_loaded_lossy() -> Result<Vec<Result<Self>>> {
+        Ok(Self::iter_loaded()?.collect())
+    }
+
+    /// Lazily iterate over currently loaded dynamic kernel modules, and any
+    /// errors reading them.
+    ///
+    /// Modules are only read as the iterator advances, so searching for a
+    /// single module with [`Iterator::find`] can stop early.
+    ///
+    /// See [`LoadedModule::get_loaded_lossy`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// - I/O
+    pub fn iter_loaded() -> Result<impl Iterator<Item = Result<Self>>> {
         let dir = Path::new(SYSFS_PATH).join("module");
-        let mut mods = Vec::new();
-        //
-        for module in fs::read_dir(dir)? {
-            let module: DirEntry = module?;
-            let m = Self::from_dir(&module.path())?;
-            if let Type::BuiltIn = m.module_type() {
-                continue;
+        Ok(fs::read_dir(dir)?.filter_map(|module| {
+            let m = module
+                .map_err(Into::into)
+                .and_then(|module: DirEntry| Self::from_dir(&module.path()));
+            match m.as_ref().map(|m| m.module_type()) {
+                Ok(Type::BuiltIn) => None,
+                _ => Some(m),
             }
-            mods.push(m);
+        }))
+    }
+
+    /// Get currently loaded dynamic kernel modules from `/proc/modules`.
+    ///
+    /// This reads a single file, instead of a directory per module like
+    /// [`LoadedModule::get_loaded`], and is the only place to get a modules
+    /// base address.
+    ///
+    /// See [`ProcModule`] for details.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    /// - If any entries are invalid
+    pub fn from_proc() -> Result<Vec<ProcModule>> {
+        let mut mods = Vec::new();
+        for line in fs::read_to_string(Path::new(PROC_PATH).join("modules"))?.lines() {
+            mods.push(ProcModule::from_line(line)?);
         }
         Ok(mods)
     }
@@ -193,6 +538,54 @@ impl LoadedModule {
         Ok(())
     }
 
+    /// Unload the module, if it isn't in use.
+    ///
+    /// Unlike [`LoadedModule::unload`], the module is given back on failure.
+    ///
+    /// # Errors
+    ///
+    /// - [`ModuleError::UnloadError`] if the module is in use, or failed to
+    ///   unload. The module is still loaded.
+    pub fn try_unload(self) -> Result<(), (LoadedModule, Box<ModuleError>)> {
+        let name = self.name.clone();
+        let err = |e: &dyn Display| Box::new(ModuleError::UnloadError(name.clone(), e.to_string()));
+        match self.ref_count() {
+            Ok(Some(1..)) => return Err((self, err(&IN_USE))),
+            Err(e) => return Err((self, err(&e))),
+            _ => (),
+        }
+        let c_name = CString::new(self.name.as_str()).expect("Module name had null bytes");
+        match delete_module(&c_name, DeleteModuleFlags::O_NONBLOCK) {
+            Ok(()) => Ok(()),
+            Err(e) => Err((self, err(&e))),
+        }
+    }
+
+    /// Reload the module from disk with new `param`eters.
+    ///
+    /// This finds the module file using [`ModuleFile::from_name`],
+    /// unloads the module, and then loads the file.
+    ///
+    /// See [`ModuleFile::load`] for details on `param`.
+    ///
+    /// # Errors
+    ///
+    /// - [`ModuleError::ReloadError`] if the module is in use, it's file
+    ///   couldn't be found, or it failed to unload. The module is still loaded.
+    /// - [`ModuleError::ReloadUnloaded`] if the module was unloaded, but failed
+    ///   to load again. The module is no longer loaded.
+    pub fn reload(self, param: &str) -> Result<LoadedModule> {
+        let name = self.name.clone();
+        let still_loaded = |e: &dyn Display| ModuleError::ReloadError(name.clone(), e.to_string());
+        let file = ModuleFile::from_name(&self.name).map_err(|e| still_loaded(&e))?;
+        if let Some(1..) = self.ref_count().map_err(|e| still_loaded(&e))? {
+            return Err(still_loaded(&IN_USE).into());
+        }
+        self.unload().map_err(|e| still_loaded(&e))?;
+        file.load(param)
+            .map_err(|e| ModuleError::ReloadUnloaded(name, e.to_string()).into())
+    }
+
     /// Forcefully unload a kernel module.
     ///
     /// # Safety
@@ -232,7 +625,7 @@ impl LoadedModule {
     ///
     /// The key will be the parameter name and the value is it's data.
     ///
-    /// There is currently no way to *write* parameters.
+    /// See [`LoadedModule::set_parameter_display`] to write parameters.
     ///
     /// # Stability
     ///
@@ -264,6 +657,76 @@ impl LoadedModule {
         Ok(map)
     }
 
+    /// Read the module parameter `name` as a string.
+    ///
+    /// The trailing newline the kernel adds is removed.
+    ///
+    /// See [`LoadedModule::parameters`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// - [`ModuleError::InvalidParameter`] if `name` is not a valid parameter
+    ///   name
+    /// - If I/O does
+    /// - If the parameter is not valid UTF-8
+    pub fn parameter_string(&self, name: &str) -> Result<String> {
+        let mut s = String::from_utf8(fs::read(self.parameter_path(name)?)?)?;
+        if s.ends_with('\n') {
+            s.pop();
+        }
+        Ok(s)
+    }
+
+    /// Read and parse the module parameter `name`.
+    ///
+    /// See [`LoadedModule::parameter_string`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// - If the parameter couldn't be parsed as `T`
+    /// - See [`LoadedModule::parameter_string`]
+    pub fn parameter_parse<T>(&self, name: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        Ok(self.parameter_string(name)?.parse()?)
+    }
+
+    /// Write `value` to the module parameter `name`.
+    ///
+    /// `value` is written without a trailing newline.
+    ///
+    /// See [`LoadedModule::set_parameter`] for details.
+    pub fn set_parameter_display<T: Display>(&self, name: &str, value: T) -> Result<()> {
+        self.set_parameter(name, value.to_string().as_bytes())
+    }
+
+    /// Write the raw bytes `value` to the module parameter `name`.
+    ///
+    /// `value` is written in a single write, as the kernel expects.
+    ///
+    /// # Errors
+    ///
+    /// - [`ModuleError::InvalidParameter`] if `name` is not a valid parameter
+    ///   name
+    /// - [`ModuleError::ParameterPermission`] if the parameter is read-only,
+    ///   or you don't have permission to write it
+    /// - If I/O does, such as if the kernel rejected `value`
+    pub fn set_parameter(&self, name: &str, value: &[u8]) -> Result<()> {
+        let path = self.parameter_path(name)?;
+        let perm = |e: io::Error| match e.raw_os_error() {
+            Some(libc::EACCES) | Some(libc::EPERM) => ModuleError::ParameterPermission(name.into()),
+            _ => ModuleError::Io(e),
+        };
+        let mut f = fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(perm)?;
+        f.write_all(value).map_err(perm)?;
+        Ok(())
+    }
+
     /// Module reference count.
     ///
     /// If the module is built-in, or if the kernel was not built with
@@ -273,10 +736,9 @@ impl LoadedModule {
     ///
     /// - If I/O does
     pub fn ref_count(&self) -> Result<Option<u32>> {
-        match read_attribute(&self.path, "refcnt") {
-            Ok(s) => Ok(Some(s.parse()?)),
-            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
-            Err(e) => Err(e.into()),
+        match read_attr_optional(&self.path, "refcnt")? {
+            Some(s) => Ok(Some(s.parse()?)),
+            None => Ok(None),
         }
     }
 
@@ -286,28 +748,20 @@ impl LoadedModule {
     ///
     /// - If I/O does
     pub fn size(&self) -> Result<u64> {
-        Ok(read_attribute(&self.path, "coresize")?.parse()?)
+        Ok(read_attr_parsed(&self.path, "coresize")?)
     }
 
     /// Module taint.
     ///
-    /// See [`Taint`] for details.
+    /// See [`TaintFlags`] for details.
+    ///
+    /// Unknown taint flags are reported as [`TaintFlags::UNKNOWN`]
     ///
     /// # Errors
     ///
     /// - On I/O
-    /// - On unexpected taint flags
-    pub fn taint(&self) -> Result<Taint> {
-        // Can a module have *multiple* taint flags?
-        match &*read_attribute(&self.path, "taint")? {
-            "P" => Ok(Taint::Proprietary),
-            "O" => Ok(Taint::OutOfTree),
-            "F" => Ok(Taint::Forced),
-            "C" => Ok(Taint::Staging),
-            "E" => Ok(Taint::Unsigned),
-            "" => Ok(Taint::Clean),
-            _ => Err("Unexpected".into()),
-        }
+    pub fn taint(&self) -> Result<TaintFlags> {
+        Ok(parse_taint(&read_attr(&self.path, "taint")?))
     }
 
     /// Names of other modules that use/reference this one.
@@ -333,6 +787,77 @@ impl LoadedModule {
         Ok(v)
     }
 
+    /// Number of modules using this module.
+    ///
+    /// This is faster than `holders()?.len()`, and never panics.
+    ///
+    /// # Note
+    ///
+    /// This differs from [`LoadedModule::ref_count`], which also counts
+    /// other users, such as open device files. If the reference count is
+    /// higher than this, the module is in use by something other than
+    /// modules.
+    ///
+    /// Built-in modules have no holders, and return `0`.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    pub fn holder_count(&self) -> Result<usize> {
+        let dir = match fs::read_dir(self.path.join("holders")) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        let mut count = 0;
+        for re in dir {
+            re?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Addresses each ELF section of the module was loaded at.
+    ///
+    /// The key will be the section name, such as `.text`, and the value is
+    /// it's kernel virtual address.
+    ///
+    /// If the module has no sections, such as built-in modules, this will be
+    /// empty.
+    ///
+    /// # Note
+    ///
+    /// Reading these requires `CAP_SYS_ADMIN`.
+    /// Without it they will either fail to be read, or all be zero.
+    ///
+    /// # Panics
+    ///
+    /// - If a section name is not valid UTF-8
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    /// - If an address is invalid
+    pub fn sections(&self) -> Result<HashMap<String, u64>> {
+        let mut map = HashMap::new();
+        let path = self.path.join("sections");
+        if path.exists() {
+            for entry in fs::read_dir(path)? {
+                let entry: DirEntry = entry?;
+                let addr = fs::read_to_string(entry.path())?;
+                let addr = addr.trim();
+                map.insert(
+                    entry
+                        .file_name()
+                        .into_string()
+                        .expect("Module section not valid UTF-8"),
+                    u64::from_str_radix(addr.strip_prefix("0x").unwrap_or(addr), 16)?,
+                );
+            }
+        }
+        Ok(map)
+    }
+
     /// Get a [`ModuleFile`] from a [`LoadedModule`]
     ///
     /// This can be useful to get information, such as parameter types, about a
@@ -352,6 +877,52 @@ impl LoadedModule {
         ModuleFile::from_name(&self.name)
     }
 
+    /// Identifier of the signature the module was verified with.
+    ///
+    /// This is only available if the kernel was built with
+    /// `CONFIG_MODULE_SIG`, and the module was signed, otherwise it's [`None`]
+    ///
+    /// # Note
+    ///
+    /// This uses the undocumented `sig_id` file.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    pub fn signature_id(&self) -> Result<Option<String>> {
+        Ok(read_attr_optional(&self.path, "sig_id")?)
+    }
+
+    /// Key the module was signed with.
+    ///
+    /// See [`LoadedModule::signature_id`] for details.
+    ///
+    /// # Note
+    ///
+    /// This uses the undocumented `sig_key` file.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    pub fn signature_key(&self) -> Result<Option<String>> {
+        Ok(read_attr_optional(&self.path, "sig_key")?)
+    }
+
+    /// Hash algorithm the module signature used.
+    ///
+    /// See [`LoadedModule::signature_id`] for details.
+    ///
+    /// # Note
+    ///
+    /// This uses the undocumented `sig_hashalgo` file.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    pub fn signature_hash_algo(&self) -> Result<Option<String>> {
+        Ok(read_attr_optional(&self.path, "sig_hashalgo")?)
+    }
+
     /// Module status.
     ///
     /// # Note
@@ -363,13 +934,38 @@ impl LoadedModule {
     ///
     /// - If I/O does
     pub fn status(&self) -> Result<Status> {
-        match &*read_attribute(&self.path, "initstate")? {
+        match &*read_attr(&self.path, "initstate")? {
             "live" => Ok(Status::Live),
             "coming" => Ok(Status::Coming),
             "going" => Ok(Status::Going),
             s => Ok(Status::Unknown(s.into())),
         }
     }
+
+    /// Wait up to `timeout` for the module to become [`Status::Live`].
+    ///
+    /// Modules may still be running their init, such as while loading
+    /// firmware, after [`ModuleFile::load`] returns.
+    ///
+    /// Returns `true` if the module became live, or `false` on timeout.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does, see [`LoadedModule::status`]
+    pub fn wait_until_live(&self, timeout: Duration) -> Result<bool> {
+        const INTERVAL: Duration = Duration::from_millis(50);
+        let start = Instant::now();
+        loop {
+            if let Status::Live = self.status()? {
+                return Ok(true);
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Ok(false);
+            }
+            thread::sleep(INTERVAL.min(timeout - elapsed));
+        }
+    }
 }
 
 // Private
@@ -393,9 +989,8 @@ impl LoadedModule {
     fn from_dir(path: &Path) -> Result<Self> {
         let name = path
             .file_name()
-            .expect("Missing module name")
-            .to_str()
-            .expect("Invalid module name");
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| ModuleError::InvalidModule(NAME.into()))?;
         // `/sys/modules` seems to always use `_` in paths?
         let path = path.with_file_name(name.replace('-', "_"));
         if !path.exists() {
@@ -421,47 +1016,168 @@ impl LoadedModule {
         };
         Ok(s)
     }
+
+    /// Path to the parameter `name`
+    ///
+    /// # Errors
+    ///
+    /// - [`ModuleError::InvalidParameter`] if `name` is empty, `.`, `..`,
+    ///   or contains a `/`, and so would escape `parameters`
+    fn parameter_path(&self, name: &str) -> Result<PathBuf> {
+        if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+            return Err(ModuleError::InvalidParameter(name.into()).into());
+        }
+        Ok(self.path.join("parameters").join(name))
+    }
 }
 
 impl UEvent for LoadedModule {
-    fn write(&self, action: UEventAction, uuid: Option<String>, args: HashMap<String, String>) {
+    fn write(
+        &self,
+        action: UEventAction,
+        uuid: Option<String>,
+        args: HashMap<String, String>,
+    ) -> io::Result<()> {
         write_uevent(&self.path.join("uevent"), action, uuid, args)
     }
-    fn read(&self) -> HashMap<String, String> {
+    fn read(&self) -> io::Result<HashMap<String, String>> {
         read_uevent(&self.path.join("uevent"))
     }
 }
 
-/// A Linux Kernel Module file on disk.
+/// A loaded kernel module, as described by `/proc/modules`.
 ///
-/// On construction information about the module is read and saved.
-///
-/// But the file may change on disk or even be removed, so you can use
-/// `ModuleFile::refresh` to update the information or show an error if it's
-/// been removed.
-#[derive(Debug)]
-pub struct ModuleFile {
-    name: String,
-    path: PathBuf,
-    //
-    info: Option<ModInfo>,
-    signature: bool,
-}
+/// See [`LoadedModule::from_proc`]
+#[derive(Debug, Clone)]
+pub struct ProcModule {
+    /// The name of the Module
+    pub name: String,
 
-// Public methods
-impl ModuleFile {
-    /// Refresh information on the module
-    ///
+    /// Module size in bytes
+    pub size: u64,
+
+    /// Module reference count.
+    ///
+    /// If the kernel was not built with `CONFIG_MODULE_UNLOAD`, this will be
+    /// [`None`]
+    pub ref_count: Option<u32>,
+
+    /// Names of other modules that use/reference this one.
+    ///
+    /// See [`LoadedModule::holders`]
+    pub holders: Vec<String>,
+
+    /// Module status.
+    pub status: Status,
+
+    /// Address the module was loaded at.
+    ///
+    /// This requires `CAP_SYSLOG`, and is [`None`] otherwise.
+    pub base_address: Option<u64>,
+
+    /// Module taint.
+    pub taint: TaintFlags,
+}
+
+// Private
+impl ProcModule {
+    /// Parse a `/proc/modules` line.
+    ///
+    /// This is formatted as `name size refcnt holders state address (taint)`,
+    /// see `m_show` in `kernel/module/procfs.c`.
+    ///
+    /// `holders` is a comma separated list with a trailing comma, or `-` if
+    /// empty. `refcnt` and `holders` are both `-` without
+    /// `CONFIG_MODULE_UNLOAD`. The taint flags are only present if the module
+    /// is tainted.
+    fn from_line(line: &str) -> Result<Self> {
+        let invalid = || ModuleError::InvalidModule(PROC_MODULES.into());
+        let mut i = line.split_whitespace();
+        let name = i.next().ok_or_else(invalid)?.to_owned();
+        let size = i.next().ok_or_else(invalid)?.parse()?;
+        let ref_count = match i.next().ok_or_else(invalid)? {
+            "-" => None,
+            s => Some(s.parse()?),
+        };
+        let holders = i
+            .next()
+            .ok_or_else(invalid)?
+            .split(',')
+            .filter(|s| !s.is_empty() && *s != "-" && *s != "[permanent]")
+            .map(Into::into)
+            .collect();
+        let status = match i.next().ok_or_else(invalid)? {
+            "Live" => Status::Live,
+            "Loading" => Status::Coming,
+            "Unloading" => Status::Going,
+            s => Status::Unknown(s.into()),
+        };
+        let addr = i.next().ok_or_else(invalid)?;
+        let addr = u64::from_str_radix(addr.strip_prefix("0x").unwrap_or(addr), 16)?;
+        let taint = match i.next() {
+            Some(s) => parse_taint(
+                s.strip_prefix('(')
+                    .and_then(|s| s.strip_suffix(')'))
+                    .ok_or_else(invalid)?,
+            ),
+            None => TaintFlags::empty(),
+        };
+        Ok(Self {
+            name,
+            size,
+            ref_count,
+            holders,
+            status,
+            // Addresses are hidden as zero without permission.
+            base_address: if addr == 0 { None } else { Some(addr) },
+            taint,
+        })
+    }
+}
+
+/// A Linux Kernel Module file on disk.
+///
+/// On construction information about the module is read and saved.
+///
+/// But the file may change on disk or even be removed, so you can use
+/// `ModuleFile::refresh` to update the information or show an error if it's
+/// been removed.
+#[derive(Debug)]
+pub struct ModuleFile {
+    name: String,
+    path: PathBuf,
+    //
+    info: Option<ModInfo>,
+    signature: bool,
+}
+
+// Public methods
+impl ModuleFile {
+    /// Refresh information on the module
+    ///
     /// # Errors
     ///
     /// - If the file no longer exists
     /// - If the module or any of it's information is invalid
     pub fn refresh(&mut self) -> Result<()> {
-        let img = self.read()?;
-        self.info = Some(self._info(&img)?);
-        self.signature = img.ends_with(SIGNATURE_MAGIC);
-        //
-        Ok(())
+        // Uncompressed modules can be very large, and only `.modinfo` is needed,
+        // so only read what's needed instead of the whole file.
+        if self.path.extension() == Some("ko".as_ref()) {
+            let file = fs::File::open(&self.path)?;
+            self.info = Some(ModInfo::from_sections(InfoSections::from_file(&file)?)?);
+            let mut tail = [0; SIGNATURE_MAGIC.len()];
+            self.signature = match file.metadata()?.len().checked_sub(tail.len() as u64) {
+                Some(offset) => {
+                    file.read_exact_at(&mut tail, offset)?;
+                    tail == SIGNATURE_MAGIC
+                }
+                None => false,
+            };
+            Ok(())
+        } else {
+            let img = self.read()?;
+            self.refresh_from(&img)
+        }
     }
 
     /// Search `/lib/modules/(uname -r)` for the module `name`.
@@ -471,52 +1187,129 @@ impl ModuleFile {
     /// - If the module couldn't be found
     /// - See [`ModuleFile::refresh`]
     pub fn from_name(name: &str) -> Result<Self> {
-        Self::from_name_with_uname(name, uname().release())
+        Self::from_name_with_uname(name, kernel_release())
     }
 
     /// Search `lib/modules/<uname>` for the module `name`.
     ///
     /// See [`ModuleFile::from_name`] for more details.
+    ///
+    /// # Note
+    ///
+    /// This method performs automatic underscore conversion, `snd_hda_intel`
+    /// will find `snd-hda-intel.ko`.
     pub fn from_name_with_uname(name: &str, uname: &str) -> Result<Self> {
-        let path = Path::new(MODULE_PATH).join(uname);
-        for entry in WalkDir::new(path) {
-            let entry = entry.map_err(|e| ModuleError::Io(e.into()))?;
-            if !entry.file_type().is_file() {
-                continue;
-            }
-            // Get the module filename without any extensions.
-            // Modules are `.ko` but can be compressed, `.ko.xz`.
-            let m_name = entry
-                .path()
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .map(|s| s.split_once('.').map_or(s, |x| x.0))
-                .ok_or_else(|| ModuleError::InvalidModule(INVALID_EXTENSION.into()))?;
-            if m_name == name {
-                let mut s = Self {
-                    name: name.into(),
-                    path: entry.into_path(),
-                    info: None,
-                    signature: false,
-                };
-                s.refresh()?;
-                return Ok(s);
+        match Self::search(name, uname, false)?.pop() {
+            Some(path) => Self::with_name(name, path),
+            None => Err(ModuleError::LoadError(name.into(), NOT_FOUND.into()).into()),
+        }
+    }
+
+    /// Search `/lib/modules/(uname -r)` for every module file named `name`,
+    /// and lazily iterate over them and any errors reading them.
+    ///
+    /// Multiple files can exist for the same module, such as when a DKMS
+    /// build shadows an in-tree module.
+    ///
+    /// Modules are returned in the order `modprobe` prefers them by default,
+    /// those in `updates/` first, followed by the rest. Each group is sorted
+    /// by path.
+    ///
+    /// All matching files are found up front, but each is only read as the
+    /// iterator advances, so picking the first with [`Iterator::next`] only
+    /// reads one module.
+    ///
+    /// The iterator is empty if no modules were found.
+    ///
+    /// # Errors
+    ///
+    /// - If searching `/lib/modules/(uname -r)` fails
+    /// - Each item, see [`ModuleFile::refresh`]
+    pub fn all_from_name(name: &str) -> Result<impl Iterator<Item = Result<Self>> + '_> {
+        Self::all_from_name_with_uname(name, kernel_release())
+    }
+
+    /// Search `lib/modules/<uname>` for every module file named `name`.
+    ///
+    /// See [`ModuleFile::all_from_name`] for more details.
+    pub fn all_from_name_with_uname<'a>(
+        name: &'a str,
+        uname: &str,
+    ) -> Result<impl Iterator<Item = Result<Self>> + 'a> {
+        let updates = Path::new(MODULE_PATH).join(uname).join("updates");
+        let mut paths = Self::search(name, uname, true)?;
+        paths.sort_by(|a, b| (!a.starts_with(&updates), a).cmp(&(!b.starts_with(&updates), b)));
+        Ok(paths
+            .into_iter()
+            .map(move |path| Self::with_name(name, path)))
+    }
+
+    /// Search `/lib/modules/(uname -r)/modules.alias` for modules matching
+    /// `alias`, such as `pci:v000010DEd...`.
+    ///
+    /// This file is generated by `depmod`, and contains shell style patterns
+    /// for every alias a module claims.
+    ///
+    /// Every matching module is returned, in the order they appear in
+    /// `modules.alias`.
+    ///
+    /// # Errors
+    ///
+    /// - If `modules.alias` couldn't be read
+    /// - If `alias` has any internal null bytes
+    /// - See [`ModuleFile::from_name`]
+    pub fn from_alias(alias: &str) -> Result<Vec<Self>> {
+        Self::from_alias_with_uname(alias, kernel_release())
+    }
+
+    /// Search `lib/modules/<uname>/modules.alias` for modules matching
+    /// `alias`.
+    ///
+    /// See [`ModuleFile::from_alias`] for more details.
+    pub fn from_alias_with_uname(alias: &str, uname: &str) -> Result<Vec<Self>> {
+        let path = Path::new(MODULE_PATH).join(uname).join("modules.alias");
+        let alias = CString::new(alias)?;
+        let mut names = Vec::new();
+        // Lines are formatted as `alias <pattern> <module>`
+        let aliases = fs::read_to_string(path)?;
+        for line in aliases.lines() {
+            let mut i = line.split_whitespace();
+            let (pattern, name) = match (i.next(), i.next(), i.next()) {
+                (Some("alias"), Some(pattern), Some(name)) => (pattern, name),
+                _ => continue,
+            };
+            let pattern = match CString::new(pattern) {
+                Ok(p) => p,
+                Err(_) => continue,
+            };
+            // Safe because both are valid null terminated strings.
+            let matched = unsafe { libc::fnmatch(pattern.as_ptr(), alias.as_ptr(), 0) } == 0;
+            if matched && !names.contains(&name) {
+                names.push(name);
             }
         }
-        Err(ModuleError::LoadError(name.into(), NOT_FOUND.into()).into())
+        names
+            .into_iter()
+            .map(|name| Self::from_name_with_uname(name, uname))
+            .collect()
     }
 
     /// Use the file at `path` as a module.
     ///
+    /// The module name is the file name without any extensions, so both
+    /// `foo.ko` and `foo.ko.xz` are named `foo`.
+    ///
     /// # Errors
     ///
     /// - if `path` does not exist
     /// - if `path` is not a valid module.
     pub fn from_path(path: &Path) -> Result<Self> {
         let mut s = Self {
+            // Modules are `.ko` but can be compressed, `.ko.xz`.
             name: path
                 .file_stem()
                 .and_then(|s| s.to_str())
+                .map(|s| s.split_once('.').map_or(s, |x| x.0))
                 .ok_or_else(|| {
                     ModuleError::LoadError(path.display().to_string(), NOT_FOUND.into())
                 })?
@@ -530,6 +1323,56 @@ impl ModuleFile {
         Ok(s)
     }
 
+    /// Get every module that must be loaded before this one, followed by this
+    /// module, in the order they must be loaded.
+    ///
+    /// This uses the `modules.dep` file generated by `depmod`, found in the
+    /// nearest parent directory of [`ModuleFile::path`] containing one,
+    /// usually `/lib/modules/<uname>`.
+    ///
+    /// # Errors
+    ///
+    /// - If `modules.dep` couldn't be found or read
+    /// - If this module, or any of its dependencies, aren't in `modules.dep`
+    /// - If the dependencies contain a cycle
+    /// - See [`ModuleFile::from_path`]
+    pub fn dependency_order(&self) -> Result<Vec<ModuleFile>> {
+        let root = self
+            .path
+            .ancestors()
+            .skip(1)
+            .find(|p| p.join("modules.dep").is_file())
+            .ok_or_else(|| ModuleError::LoadError(self.name.clone(), MODULES_DEP.into()))?;
+        let deps = fs::read_to_string(root.join("modules.dep"))?;
+        // Lines are formatted as `<module>: <dependency>...`,
+        // with paths relative to `root`, or absolute for older `depmod`.
+        let graph: HashMap<&str, Vec<&str>> = deps
+            .lines()
+            .filter_map(|l| l.split_once(':'))
+            .map(|(module, deps)| {
+                (
+                    Self::relative_dependency(root, module.trim()),
+                    deps.split_whitespace()
+                        .map(|d| Self::relative_dependency(root, d))
+                        .collect(),
+                )
+            })
+            .collect();
+        let this = self
+            .path
+            .strip_prefix(root)
+            .ok()
+            .and_then(|p| p.to_str())
+            .ok_or_else(|| ModuleError::LoadError(self.name.clone(), NOT_FOUND.into()))?;
+
+        let mut order = Vec::new();
+        Self::visit_dependencies(this, &graph, &mut Vec::new(), &mut order)?;
+        order
+            .into_iter()
+            .map(|p| Self::from_path(&root.join(p)))
+            .collect()
+    }
+
     /// Load this kernel module, and return the [`LoadedModule`] describing it.
     ///
     /// # Arguments
@@ -551,6 +1394,9 @@ impl ModuleFile {
     ///
     /// Kernel modules may be compressed, and depending on crate features this
     /// function may automatically decompress it.
+    ///
+    /// The module may not be [`Status::Live`] yet when this returns,
+    /// see [`LoadedModule::wait_until_live`].
     pub fn load(&self, param: &str) -> Result<LoadedModule> {
         let img = self.read()?;
         // FIXME: ModuleError::AlreadyLoaded
@@ -563,6 +1409,33 @@ impl ModuleFile {
         LoadedModule::from_dir(&Path::new(SYSFS_PATH).join("module").join(&self.name))
     }
 
+    /// Load this kernel module with `params`, as `(name, value)` pairs, and
+    /// return the [`LoadedModule`] describing it.
+    ///
+    /// Values containing whitespace are quoted.
+    ///
+    /// See [`ModuleFile::load`] for more details.
+    ///
+    /// # Errors
+    ///
+    /// - [`ModuleError::LoadError`] if any name or value has a `0` byte, or
+    ///   can't be passed to the kernel, such as names containing `=`, or
+    ///   values containing `"`.
+    /// - See [`ModuleFile::load`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use linapi::system::modules::*;
+    /// let m = ModuleFile::from_name("MyModule").unwrap();
+    /// let loaded = m
+    ///     .load_with_params(&[("my_param", "1"), ("label", "hello world")])
+    ///     .unwrap();
+    /// ```
+    pub fn load_with_params(&self, params: &[(&str, &str)]) -> Result<LoadedModule> {
+        self.load(&self.param_string(params)?)
+    }
+
     /// Force load this kernel module, and return the [`LoadedModule`]
     /// describing it.
     ///
@@ -615,121 +1488,257 @@ impl ModuleFile {
     pub fn has_signature(&self) -> bool {
         self.signature
     }
+
+    /// Split the module into its body and appended signature, if any.
+    ///
+    /// The body is everything before the signature, and the signature is
+    /// the raw signature data, usually PKCS#7, without the trailing
+    /// `struct module_signature` and magic.
+    ///
+    /// The module is decompressed first, if needed.
+    ///
+    /// # Errors
+    ///
+    /// - [`ModuleError::InvalidModule`] if the signature is invalid
+    /// - If the module couldn't be read or decompressed
+    pub fn signed_payload(&self) -> Result<(Vec<u8>, Option<Vec<u8>>)> {
+        let img = self.read()?;
+        let (body, sig) = split_signature(&img)?;
+        Ok((body.to_vec(), sig.map(|s| s.to_vec())))
+    }
+
+    /// Get the module without any appended signature.
+    ///
+    /// See [`ModuleFile::signed_payload`] for details.
+    pub fn strip_signature(&self) -> Result<Vec<u8>> {
+        Ok(self.signed_payload()?.0)
+    }
+
+    /// Append the PKCS#7 DER signature `pkcs7_der` to the module `body`,
+    /// like `scripts/sign-file` does.
+    ///
+    /// `body` should not already be signed, see
+    /// [`ModuleFile::strip_signature`].
+    ///
+    /// # Examples
+    ///
+    /// Re-sign a module with an externally produced signature
+    ///
+    /// ```rust,no_run
+    /// # use linapi::system::modules::*;
+    /// let m = ModuleFile::from_name("MyModule").unwrap();
+    /// let body = m.strip_signature().unwrap();
+    /// let sig = std::fs::read("MyModule.p7s").unwrap();
+    /// std::fs::write("MyModule.ko", ModuleFile::append_signature(&body, &sig)).unwrap();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - If `pkcs7_der` is larger than 4GiB
+    pub fn append_signature(body: &[u8], pkcs7_der: &[u8]) -> Vec<u8> {
+        // From `linux/module_signature.h`
+        const PKEY_ID_PKCS7: u8 = 2;
+        let sig_len: u32 = pkcs7_der.len().try_into().expect("Signature was too large");
+        let mut img = Vec::with_capacity(
+            body.len() + pkcs7_der.len() + SIGNATURE_INFO_LEN + SIGNATURE_MAGIC.len(),
+        );
+        img.extend_from_slice(body);
+        img.extend_from_slice(pkcs7_der);
+        // `struct module_signature`. The algorithm, hash, signer, and key id
+        // are all in the PKCS#7 data, so they're zero.
+        img.extend_from_slice(&[0, 0, PKEY_ID_PKCS7, 0, 0, 0, 0, 0]);
+        img.extend_from_slice(&sig_len.to_be_bytes());
+        img.extend_from_slice(SIGNATURE_MAGIC);
+        img
+    }
+
+    /// Check `params`, as `(name, value)` pairs, against the parameters
+    /// declared in [`ModInfo::parameters`], before loading.
+    ///
+    /// See [`ModParam::is_valid_value`] for how values are checked.
+    ///
+    /// # Errors
+    ///
+    /// - [`ParamError::UnknownName`] if a parameter isn't declared by the module
+    /// - [`ParamError::TypeMismatch`] if a value is invalid for its type
+    pub fn validate_params(&self, params: &[(&str, &str)]) -> Result<(), ParamError> {
+        let declared = &self.info().parameters;
+        for (name, value) in params {
+            let param = declared
+                .iter()
+                .find(|p| p.name == *name)
+                .ok_or_else(|| ParamError::UnknownName((*name).into()))?;
+            if !param.is_valid_value(value) {
+                return Err(ParamError::TypeMismatch(
+                    (*name).into(),
+                    param.type_.clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether this module is compatible with the running kernel,
+    /// before trying to load it.
+    ///
+    /// See [`ModInfo::vermagic_matches`] for details.
+    ///
+    /// # Errors
+    ///
+    /// - [`ModuleError::Incompatible`] if the modules vermagic doesn't start
+    ///   with the running kernel release
+    pub fn check_compatible(&self) -> Result<()> {
+        let info = self.info();
+        if !info.vermagic_matches() {
+            return Err(ModuleError::Incompatible {
+                module: self.name.clone(),
+                kernel_release: kernel_release().into(),
+                vermagic: info.version_magic.clone(),
+            }
+            .into());
+        }
+        Ok(())
+    }
 }
 
 // Private methods
 impl ModuleFile {
-    fn read(&self) -> Result<Vec<u8>> {
-        self.decompress(fs::read(&self.path)?)
+    /// Refresh information on the module from the module image `img`
+    fn refresh_from(&mut self, img: &[u8]) -> Result<()> {
+        self.info = Some(ModInfo::from_bytes(img)?);
+        self.signature = img.ends_with(SIGNATURE_MAGIC);
+        //
+        Ok(())
     }
 
-    fn _info(&self, img: &[u8]) -> Result<ModInfo> {
-        let elf = ElfFile::new(img).map_err(|e| ModuleError::InvalidModule(e.to_string()))?;
-        let sect = elf
-            .find_section_by_name(".modinfo")
-            .ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
-        let data = sect.raw_data(&elf);
-        //
-        let mut map = HashMap::new();
-        for kv in BufRead::split(data, b'\0') {
-            let kv = kv?;
-            let s = String::from_utf8(kv).map_err(|e| ModuleError::InvalidModule(e.to_string()))?;
-            let mut s = s.splitn(2, '=');
-            //
-            let key = s
-                .next()
-                .map(|s| s.to_string())
-                .ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
-            let value = s
-                .next()
-                .map(|s| s.to_string())
-                .ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
-            let vec = map.entry(key).or_insert_with(Vec::new);
-            if !value.is_empty() {
-                vec.push(value);
+    /// Build a kernel parameter string from `params`.
+    ///
+    /// The kernel has no escaping, so values can only be quoted.
+    fn param_string(&self, params: &[(&str, &str)]) -> Result<String> {
+        let err = |e: &str| ModuleError::LoadError(self.name.clone(), e.into());
+        let mut s = String::new();
+        for (name, value) in params {
+            if name.contains('\0') || value.contains('\0') {
+                return Err(err("parameter contained a null byte").into());
+            }
+            if name.is_empty() || name.contains(|c: char| c == '=' || c == '"' || c.is_whitespace())
+            {
+                return Err(err(&format!("invalid parameter name `{}`", name)).into());
+            }
+            if value.contains('"') {
+                return Err(err(&format!("invalid value for parameter `{}`", name)).into());
+            }
+            if !s.is_empty() {
+                s.push(' ');
+            }
+            if value.contains(char::is_whitespace) {
+                s.push_str(&format!("{}=\"{}\"", name, value));
+            } else {
+                s.push_str(&format!("{}={}", name, value));
             }
         }
-        fn y_n(s: &str) -> bool {
-            s == "Y" || s == "y"
+        Ok(s)
+    }
+
+    /// Search `lib/modules/<uname>` for the paths of module files named
+    /// `name`, stopping at the first unless `all` is true.
+    fn search(name: &str, uname: &str, all: bool) -> Result<Vec<PathBuf>> {
+        let path = Path::new(MODULE_PATH).join(uname);
+        let mut found = Vec::new();
+        for entry in WalkDir::new(path) {
+            let entry = entry.map_err(|e| ModuleError::Io(e.into()))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            // Get the module filename without any extensions.
+            // Modules are `.ko` but can be compressed, `.ko.xz`.
+            let m_name = entry
+                .path()
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|s| s.split_once('.').map_or(s, |x| x.0))
+                .ok_or_else(|| ModuleError::InvalidModule(INVALID_EXTENSION.into()))?;
+            // The kernel treats `-` and `_` in module names the same, and
+            // `modules.alias` only uses `_`, even for files named with `-`.
+            if m_name.replace('-', "_") == name.replace('-', "_") {
+                found.push(entry.into_path());
+                if !all {
+                    break;
+                }
+            }
         }
-        fn one(map: &mut HashMap<String, Vec<String>>, key: &str) -> String {
-            map.remove(key).map(|mut v| v.remove(0)).unwrap_or_default()
+        Ok(found)
+    }
+
+    /// Use the file at `path` as the module `name`.
+    fn with_name(name: &str, path: PathBuf) -> Result<Self> {
+        let mut s = Self {
+            name: name.into(),
+            path,
+            info: None,
+            signature: false,
+        };
+        s.refresh()?;
+        Ok(s)
+    }
+
+    /// Path of the `modules.dep` entry `dep` relative to `root`.
+    ///
+    /// Older versions of `depmod` wrote absolute paths, either under `root`
+    /// or under `/lib/modules/<uname>`, where `<uname>` is the name of
+    /// `root`. Other paths are returned unchanged.
+    fn relative_dependency<'a>(root: &Path, dep: &'a str) -> &'a str {
+        let path = Path::new(dep);
+        if !path.is_absolute() {
+            return dep;
         }
-        fn more(map: &mut HashMap<String, Vec<String>>, key: &str) -> Vec<String> {
-            map.remove(key).unwrap_or_default()
+        let lib = root
+            .file_name()
+            .map(|uname| Path::new("/lib/modules").join(uname));
+        path.strip_prefix(root)
+            .ok()
+            .or_else(|| path.strip_prefix(lib?).ok())
+            .and_then(|p| p.to_str())
+            .unwrap_or(dep)
+    }
+
+    /// Depth first walk of the `modules.dep` `graph` starting at `module`,
+    /// pushing modules to `order` after all of their dependencies.
+    ///
+    /// `stack` holds the modules currently being visited, to detect cycles.
+    fn visit_dependencies<'a>(
+        module: &'a str,
+        graph: &HashMap<&'a str, Vec<&'a str>>,
+        stack: &mut Vec<&'a str>,
+        order: &mut Vec<&'a str>,
+    ) -> Result<()> {
+        if order.contains(&module) {
+            return Ok(());
         }
-        //
-        let mut x = HashMap::new();
-        for (name, typ) in map
-            .remove("parmtype")
-            .unwrap_or_default()
-            .into_iter()
-            .map(|s| {
-                let mut i = s.splitn(2, ':').map(|s| s.trim().to_owned());
-                (i.next(), i.next())
-            })
-        {
-            let name: Option<String> = name;
-            let typ: Option<String> = typ;
-            // Types are reasonably guaranteed to exist because
-            // `linux/moduleparam.h` adds them for all the `module_param`
-            // macros, which define parameters.
-            let name = name.ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
-            let typ = typ.ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
-            // Parameters should not have multiple types.
-            if x.insert(name, (typ, None)).is_some() {
-                return Err(ModuleError::InvalidModule(MODINFO.into()).into());
-            };
+        if stack.contains(&module) {
+            return Err(ModuleError::DependencyCycle(module.into()).into());
         }
-        for (name, desc) in map.remove("parm").unwrap_or_default().into_iter().map(|s| {
-            let mut i = s.splitn(2, ':').map(|s| s.trim().to_owned());
-            (i.next(), i.next())
-        }) {
-            let name: Option<String> = name;
-            let desc: Option<String> = desc;
-            //
-            let name = name.ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
-            // If we've seen the parameter, which we should have it's probably a
-            // module bug otherwise, add it's description.
-            //
-            // Parameters aren't required to have descriptions.
-            x.get_mut(&name)
-                .map(|v| v.1 = desc)
-                .ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
-        }
-        let mut parameters = Vec::new();
-        for (name, (type_, description)) in x {
-            parameters.push(ModParam {
-                name,
-                type_,
-                description,
-            })
+        let deps = graph
+            .get(module)
+            .ok_or_else(|| ModuleError::LoadError(module.into(), MODULES_DEP.into()))?;
+        stack.push(module);
+        for dep in deps {
+            Self::visit_dependencies(dep, graph, stack, order)?;
         }
-        //
-        Ok(ModInfo {
-            alias: more(&mut map, "alias"),
-            soft_dependencies: more(&mut map, "softdep"),
-            license: one(&mut map, "license"),
-            authors: more(&mut map, "author"),
-            description: one(&mut map, "description"),
-            version: one(&mut map, "version"),
-            firmware: more(&mut map, "firmware"),
-            version_magic: one(&mut map, "vermagic"),
-            name: one(&mut map, "name"),
-            in_tree: y_n(&one(&mut map, "intree")),
-            retpoline: y_n(&one(&mut map, "retpoline")),
-            staging: y_n(&one(&mut map, "staging")),
-            dependencies: more(&mut map, "depends"),
-            source_checksum: one(&mut map, "srcversion"),
-            parameters,
-        })
+        stack.pop();
+        order.push(module);
+        Ok(())
+    }
+
+    fn read(&self) -> Result<Vec<u8>> {
+        self.decompress(fs::read(&self.path)?)
     }
 
     /// Decompresses a kernel module
     ///
     /// Returns `data` unchanged if not compressed.
     fn decompress(&self, data: Vec<u8>) -> Result<Vec<u8>> {
-        #[cfg(any(feature = "xz", feature = "gz", feature = "zst"))]
+        #[cfg(any(feature = "xz", feature = "gz", feature = "zst", feature = "lz4"))]
         let mut v = Vec::new();
         let ext = self
             .path
@@ -759,6 +1768,13 @@ impl ModuleFile {
                     .map_err(|e| ModuleError::InvalidModule(e.to_string()))?;
                 Ok(v)
             }
+            #[cfg(feature = "lz4")]
+            "lz4" => {
+                let mut data = Lz4Decoder::new(data.as_slice());
+                data.read_to_end(&mut v)
+                    .map_err(|e| ModuleError::InvalidModule(e.to_string()))?;
+                Ok(v)
+            }
             "ko" => Ok(data),
             _ => Err(ModuleError::InvalidModule(COMPRESSION.into()).into()),
         }
@@ -766,6 +1782,7 @@ impl ModuleFile {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ModParam {
     /// Parameter name
     pub name: String,
@@ -779,6 +1796,147 @@ pub struct ModParam {
     pub description: Option<String>,
 }
 
+// Public
+impl ModParam {
+    /// Read the current value of this parameter from the loaded `module`.
+    ///
+    /// The kernel doesn't record parameter defaults, so this is the most
+    /// reliable way to find what value a parameter has.
+    ///
+    /// Returns [`None`] if the parameter isn't exposed at runtime, which
+    /// depends on the permissions given in `module_param`.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    pub fn live_value(&self, module: &LoadedModule) -> Result<Option<Vec<u8>>> {
+        match fs::read(module.path.join("parameters").join(&self.name)) {
+            Ok(v) => Ok(Some(v)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Whether the kernel would accept `value` for this parameter.
+    ///
+    /// Parameters with unknown or custom types accept any value.
+    ///
+    /// # Implementation
+    ///
+    /// This follows the `param_set_*` functions in `kernel/params.c`.
+    pub fn is_valid_value(&self, value: &str) -> bool {
+        match self.type_.strip_prefix("array of ") {
+            Some(type_) => value.split(',').all(|v| param_value_valid(type_, v)),
+            None => param_value_valid(&self.type_, value),
+        }
+    }
+}
+
+/// Whether `value` is valid for the standard parameter type `type_`.
+fn param_value_valid(type_: &str, value: &str) -> bool {
+    // Integers are parsed with `kstrto*` using base 0, so `0x` is hex and a
+    // leading `0` is octal.
+    let int = |signed: bool| {
+        let (neg, v) = match value.strip_prefix('-') {
+            Some(v) if signed => (true, v),
+            Some(_) => return None,
+            None => (false, value.strip_prefix('+').unwrap_or(value)),
+        };
+        let (radix, v) = match v.strip_prefix("0x").or_else(|| v.strip_prefix("0X")) {
+            Some(v) => (16, v),
+            None if v.len() > 1 && v.starts_with('0') => (8, &v[1..]),
+            None => (10, v),
+        };
+        // `from_str_radix` accepts another sign, which the kernel doesn't.
+        if !v.starts_with(|c: char| c.is_digit(radix)) {
+            return None;
+        }
+        let v = i128::from_str_radix(v, radix).ok()?;
+        Some(if neg { -v } else { v })
+    };
+    let range =
+        |signed: bool, min: i128, max: i128| int(signed).is_some_and(|v| (min..=max).contains(&v));
+    match type_ {
+        "byte" => range(false, 0, u8::MAX.into()),
+        "short" => range(true, i16::MIN.into(), i16::MAX.into()),
+        "ushort" => range(false, 0, u16::MAX.into()),
+        "int" => range(true, i32::MIN.into(), i32::MAX.into()),
+        "uint" | "hexint" => range(false, 0, u32::MAX.into()),
+        "long" => range(true, libc::c_long::MIN.into(), libc::c_long::MAX.into()),
+        "ulong" => range(false, 0, libc::c_ulong::MAX.into()),
+        "ullong" => range(false, 0, u64::MAX.into()),
+        // See `kstrtobool`
+        "bool" | "invbool" => matches!(
+            value.as_bytes(),
+            [b'y' | b'Y' | b'n' | b'N' | b'1' | b'0', ..]
+                | [b'o' | b'O', b'n' | b'N' | b'f' | b'F', ..]
+        ),
+        _ => true,
+    }
+}
+
+/// Module license, see [`ModInfo::license`].
+///
+/// Only the licenses the kernel recognizes have their own variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum License {
+    /// `GPL`
+    Gpl,
+
+    /// `GPL v2`
+    GplV2,
+
+    /// `GPL and additional rights`
+    GplAndAdditionalRights,
+
+    /// `Dual BSD/GPL`
+    DualBsdGpl,
+
+    /// `Dual MIT/GPL`
+    DualMitGpl,
+
+    /// `Dual MPL/GPL`
+    DualMplGpl,
+
+    /// `Proprietary`
+    Proprietary,
+
+    /// Any other license
+    Other(String),
+}
+
+// Public
+impl License {
+    /// Whether the kernel considers this license GPL compatible.
+    ///
+    /// Modules that aren't can't use GPL-only symbols, and taint the kernel
+    /// when loaded.
+    ///
+    /// # Implementation
+    ///
+    /// This mirrors `license_is_gpl_compatible` in `linux/license.h`.
+    pub fn is_gpl_compatible(&self) -> bool {
+        !matches!(self, License::Proprietary | License::Other(_))
+    }
+}
+
+impl FromStr for License {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "GPL" => License::Gpl,
+            "GPL v2" => License::GplV2,
+            "GPL and additional rights" => License::GplAndAdditionalRights,
+            "Dual BSD/GPL" => License::DualBsdGpl,
+            "Dual MIT/GPL" => License::DualMitGpl,
+            "Dual MPL/GPL" => License::DualMplGpl,
+            "Proprietary" => License::Proprietary,
+            s => License::Other(s.into()),
+        })
+    }
+}
+
 /// Information on a [`ModuleFile`]
 ///
 /// # Notes
@@ -786,6 +1944,7 @@ pub struct ModParam {
 /// This uses the `.modinfo` ELF section, which is semi-documented in
 /// `linux/modules.h` and `MODULE_INFO`.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ModInfo {
     /// Module Aliases. Alternative names for this module.
     pub alias: Vec<String>,
@@ -833,4 +1992,395 @@ pub struct ModInfo {
 
     /// Module Parameters
     pub parameters: Vec<ModParam>,
+
+    /// The GNU build-id, hex encoded.
+    ///
+    /// This is from the `.note.gnu.build-id` ELF note, not `.modinfo`, and can
+    /// be used to match a module against its separate debug information.
+    pub build_id: Option<String>,
+
+    /// Any other information, not otherwise parsed.
+    ///
+    /// Modules can include arbitrary tags using `MODULE_INFO`.
+    /// The key will be the tag, and the value is every value for that tag.
+    pub extra: HashMap<String, Vec<String>>,
+}
+
+// Public
+impl ModInfo {
+    /// Parse the module information from the module image `img`.
+    ///
+    /// `img` must already be decompressed.
+    ///
+    /// This is useful for modules that aren't on disk, such as
+    /// those in memory or fetched over the network.
+    ///
+    /// # Errors
+    ///
+    /// - If `img` is not a valid ELF file
+    /// - If `img` has no or an invalid `.modinfo` section
+    pub fn from_bytes(img: &[u8]) -> Result<Self> {
+        Self::from_sections(InfoSections::from_bytes(img)?)
+    }
+
+    /// Whether [`ModInfo::version_magic`] matches the running kernel.
+    ///
+    /// # Note
+    ///
+    /// The running kernels full vermagic isn't exposed to userspace, so only
+    /// the kernel release, the first part of the vermagic, is compared.
+    /// The kernel may still reject the module for differing flags, such as
+    /// `SMP` or `modversions`.
+    pub fn vermagic_matches(&self) -> bool {
+        self.version_magic.split_whitespace().next() == Some(kernel_release())
+    }
+
+    /// Parsed [`ModInfo::license`].
+    pub fn license_kind(&self) -> License {
+        self.license.parse().unwrap_or_else(|e| match e {})
+    }
+
+    /// Whether [`ModInfo::license`] is GPL compatible.
+    ///
+    /// See [`License::is_gpl_compatible`] for details.
+    pub fn is_gpl_compatible(&self) -> bool {
+        self.license_kind().is_gpl_compatible()
+    }
+
+    /// Get the [`ModInfo::firmware`] files that aren't installed.
+    ///
+    /// Firmware is searched for in the same directories as the kernel,
+    /// in order
+    ///
+    /// - `/lib/firmware/updates/(uname -r)/`
+    /// - `/lib/firmware/updates/`
+    /// - `/lib/firmware/(uname -r)/`
+    /// - `/lib/firmware/`
+    ///
+    /// Firmware compressed with `xz` or `zstd`, such as `foo.bin.xz`, counts
+    /// as installed.
+    ///
+    /// # Note
+    ///
+    /// The custom path from the `firmware_class.path` kernel parameter is not
+    /// searched, and the kernel may not support loading compressed firmware.
+    pub fn missing_firmware(&self) -> Vec<String> {
+        self.missing_firmware_with_root(Path::new(FIRMWARE_PATH), kernel_release())
+    }
+
+    /// Get the [`ModInfo::firmware`] files that aren't installed in
+    /// `root/updates/<uname>/`, `root/updates/`, `root/<uname>/` or `root/`.
+    ///
+    /// See [`ModInfo::missing_firmware`] for more details.
+    pub fn missing_firmware_with_root(&self, root: &Path, uname: &str) -> Vec<String> {
+        let updates = root.join("updates");
+        let dirs = [updates.join(uname), updates, root.join(uname), root.into()];
+        self.firmware
+            .iter()
+            .filter(|f| {
+                !dirs.iter().any(|dir| {
+                    ["", ".xz", ".zst"]
+                        .iter()
+                        .any(|ext| dir.join(format!("{}{}", f, ext)).exists())
+                })
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+// Private
+impl ModInfo {
+    /// Parse the module information from `sections`.
+    fn from_sections(sections: InfoSections) -> Result<Self> {
+        let mut map = HashMap::new();
+        for kv in BufRead::split(&*sections.modinfo, b'\0') {
+            let kv = kv?;
+            let s = String::from_utf8(kv).map_err(|e| ModuleError::InvalidModule(e.to_string()))?;
+            let mut s = s.splitn(2, '=');
+            //
+            let key = s
+                .next()
+                .map(|s| s.to_string())
+                .ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
+            let value = s
+                .next()
+                .map(|s| s.to_string())
+                .ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
+            let vec = map.entry(key).or_insert_with(Vec::new);
+            if !value.is_empty() {
+                vec.push(value);
+            }
+        }
+        /// Older toolchains may use numeric booleans.
+        fn y_n(s: &str) -> bool {
+            matches!(s, "Y" | "y" | "1")
+        }
+        fn one(map: &mut HashMap<String, Vec<String>>, key: &str) -> String {
+            map.remove(key).map(|mut v| v.remove(0)).unwrap_or_default()
+        }
+        fn more(map: &mut HashMap<String, Vec<String>>, key: &str) -> Vec<String> {
+            map.remove(key).unwrap_or_default()
+        }
+        //
+        let mut x = HashMap::new();
+        for (name, typ) in map
+            .remove("parmtype")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| {
+                let mut i = s.splitn(2, ':').map(|s| s.trim().to_owned());
+                (i.next(), i.next())
+            })
+        {
+            let name: Option<String> = name;
+            let typ: Option<String> = typ;
+            // Types are reasonably guaranteed to exist because
+            // `linux/moduleparam.h` adds them for all the `module_param`
+            // macros, which define parameters.
+            let name = name.ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
+            let typ = typ.ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
+            // Parameters should not have multiple types.
+            if x.insert(name, (typ, None)).is_some() {
+                return Err(ModuleError::InvalidModule(MODINFO.into()).into());
+            };
+        }
+        for (name, desc) in map.remove("parm").unwrap_or_default().into_iter().map(|s| {
+            let mut i = s.splitn(2, ':').map(|s| s.trim().to_owned());
+            (i.next(), i.next())
+        }) {
+            let name: Option<String> = name;
+            let desc: Option<String> = desc;
+            //
+            let name = name.ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
+            // If we've seen the parameter, which we should have it's probably a
+            // module bug otherwise, add it's description.
+            //
+            // Parameters aren't required to have descriptions.
+            x.get_mut(&name)
+                .map(|v| v.1 = desc)
+                .ok_or_else(|| ModuleError::InvalidModule(MODINFO.into()))?;
+        }
+        let mut parameters = Vec::new();
+        for (name, (type_, description)) in x {
+            parameters.push(ModParam {
+                name,
+                type_,
+                description,
+            })
+        }
+        //
+        Ok(Self {
+            alias: more(&mut map, "alias"),
+            soft_dependencies: more(&mut map, "softdep"),
+            license: one(&mut map, "license"),
+            authors: more(&mut map, "author"),
+            description: one(&mut map, "description"),
+            version: one(&mut map, "version"),
+            firmware: more(&mut map, "firmware"),
+            version_magic: one(&mut map, "vermagic"),
+            name: one(&mut map, "name"),
+            in_tree: y_n(&one(&mut map, "intree")),
+            retpoline: y_n(&one(&mut map, "retpoline")),
+            staging: y_n(&one(&mut map, "staging")),
+            dependencies: more(&mut map, "depends"),
+            source_checksum: one(&mut map, "srcversion"),
+            parameters,
+            build_id: read_build_id(sections.data, &sections.notes),
+            // Must be last, after all known tags have been removed.
+            extra: map,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    const MODINFO: &[u8] = b"license=GPL\0name=test\0vermagic=6.1.0 SMP mod_unload\0";
+
+    /// Build a minimal 64-bit little endian ELF module, with a `.modinfo`
+    /// section of `modinfo` and `flags`.
+    fn elf(modinfo: &[u8], flags: u64) -> Vec<u8> {
+        let names = b"\0.modinfo\0.shstrtab\0";
+        let names_off = 64 + modinfo.len();
+        // Section headers must be aligned
+        let sh_off = (names_off + names.len() + 7) & !7;
+        let mut img = Vec::new();
+        img.extend_from_slice(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0");
+        // e_type ET_REL, e_machine x86_64, e_version
+        img.extend_from_slice(&1u16.to_le_bytes());
+        img.extend_from_slice(&62u16.to_le_bytes());
+        img.extend_from_slice(&1u32.to_le_bytes());
+        // e_entry, e_phoff, e_shoff
+        img.extend_from_slice(&[0; 16]);
+        img.extend_from_slice(&(sh_off as u64).to_le_bytes());
+        // e_flags, e_ehsize, e_phentsize, e_phnum, e_shentsize, e_shnum, e_shstrndx
+        img.extend_from_slice(&0u32.to_le_bytes());
+        for v in [64u16, 0, 0, 64, 3, 2] {
+            img.extend_from_slice(&v.to_le_bytes());
+        }
+        img.extend_from_slice(modinfo);
+        img.extend_from_slice(names);
+        img.resize(sh_off, 0);
+        // Null section, then `(sh_name, sh_type, sh_flags, sh_offset, sh_size)`
+        img.extend_from_slice(&[0; 64]);
+        for (name, type_, flags, offset, size) in [
+            (1u32, 1u32, flags, 64, modinfo.len()),
+            (10, 3, 0, names_off, names.len()),
+        ] {
+            img.extend_from_slice(&name.to_le_bytes());
+            img.extend_from_slice(&type_.to_le_bytes());
+            img.extend_from_slice(&flags.to_le_bytes());
+            // sh_addr
+            img.extend_from_slice(&[0; 8]);
+            img.extend_from_slice(&(offset as u64).to_le_bytes());
+            img.extend_from_slice(&(size as u64).to_le_bytes());
+            // sh_link, sh_info, sh_addralign, sh_entsize
+            img.extend_from_slice(&[0; 24]);
+        }
+        img
+    }
+
+    /// `Elf64_Chdr` for a compressed section of `type_`, `size` bytes when
+    /// decompressed.
+    fn chdr(type_: u32, size: usize) -> Vec<u8> {
+        let mut v = Vec::new();
+        v.extend_from_slice(&type_.to_le_bytes());
+        // ch_reserved
+        v.extend_from_slice(&[0; 4]);
+        v.extend_from_slice(&(size as u64).to_le_bytes());
+        // ch_addralign
+        v.extend_from_slice(&1u64.to_le_bytes());
+        v
+    }
+
+    #[test]
+    #[cfg(feature = "gz")]
+    fn compressed_modinfo() {
+        use flate2::{write::ZlibEncoder, Compression};
+        use std::{env, process};
+
+        let check_info = |info: &ModInfo| {
+            assert_eq!(info.name, "test");
+            assert_eq!(info.license, "GPL");
+            assert_eq!(info.version_magic, "6.1.0 SMP mod_unload");
+        };
+
+        let mut data = ZlibEncoder::new(
+            chdr(ELFCOMPRESS_ZLIB, MODINFO.len()),
+            Compression::default(),
+        );
+        data.write_all(MODINFO).unwrap();
+        let img = elf(&data.finish().unwrap(), SHF_COMPRESSED);
+        check_info(&ModInfo::from_bytes(&img).unwrap());
+
+        // `.ko` files only have the needed sections read, see `InfoSections::from_file`
+        let path = env::temp_dir().join(format!("linapi-test-{}.ko", process::id()));
+        fs::write(&path, &img).unwrap();
+        let m = ModuleFile::from_path(&path);
+        fs::remove_file(&path).unwrap();
+        check_info(m.unwrap().info());
+    }
+
+    #[test]
+    fn unsupported_compression() {
+        let mut data = chdr(u32::MAX, MODINFO.len());
+        data.extend_from_slice(MODINFO);
+        assert!(ModInfo::from_bytes(&elf(&data, SHF_COMPRESSED)).is_err());
+        // Too short for the compression header
+        assert!(ModInfo::from_bytes(&elf(&data[..8], SHF_COMPRESSED)).is_err());
+    }
+
+    #[test]
+    fn truncated() {
+        let img = elf(MODINFO, 0);
+        // Section headers are last
+        assert!(ModInfo::from_bytes(&img[..img.len() - 1]).is_err());
+        // Only the ELF header, with section headers past the end
+        let mut header = img[..64].to_vec();
+        header[40..48].copy_from_slice(&4096u64.to_le_bytes());
+        assert!(ModInfo::from_bytes(&header).is_err());
+        // `.modinfo` `sh_size` past the end
+        let mut img = img;
+        let sh_size = img.len() - 2 * 64 + 32;
+        img[sh_size..sh_size + 8].copy_from_slice(&4096u64.to_le_bytes());
+        assert!(ModInfo::from_bytes(&img).is_err());
+    }
+
+    #[test]
+    fn tiny_signed() {
+        // Too short for `struct module_signature`
+        assert!(split_signature(SIGNATURE_MAGIC).is_err());
+        assert!(ModInfo::from_bytes(&SIGNATURE_MAGIC[..10]).is_err());
+    }
+
+    #[test]
+    fn append_signature() {
+        let body = elf(MODINFO, 0);
+        let sig = b"not really PKCS#7";
+        let img = ModuleFile::append_signature(&body, sig);
+        assert_eq!(split_signature(&img).unwrap(), (&*body, Some(&sig[..])));
+        //
+        let (old, new) = (
+            ModInfo::from_bytes(&body).unwrap(),
+            ModInfo::from_bytes(&img).unwrap(),
+        );
+        assert_eq!(new.name, "test");
+        assert_eq!(new.name, old.name);
+        assert_eq!(new.license, old.license);
+        assert_eq!(new.version_magic, old.version_magic);
+    }
+
+    #[test]
+    fn absolute_dependencies() {
+        let root = env::temp_dir()
+            .join(format!("linapi-test-{}-deps", process::id()))
+            .join("6.1.0");
+        fs::create_dir_all(root.join("kernel")).unwrap();
+        for name in ["a", "b", "c"] {
+            fs::write(root.join(format!("kernel/{name}.ko")), elf(MODINFO, 0)).unwrap();
+        }
+        let abs = root.display().to_string();
+        fs::write(
+            root.join("modules.dep"),
+            format!(
+                "/lib/modules/6.1.0/kernel/a.ko: {abs}/kernel/b.ko kernel/c.ko\n\
+                 {abs}/kernel/b.ko: /lib/modules/6.1.0/kernel/c.ko\n\
+                 kernel/c.ko:\n"
+            ),
+        )
+        .unwrap();
+        let order = ModuleFile::from_path(&root.join("kernel/a.ko"))
+            .and_then(|m| m.dependency_order())
+            .map(|o| o.into_iter().map(|m| m.path).collect::<Vec<_>>());
+        fs::remove_dir_all(root.parent().unwrap()).unwrap();
+        let order = order.unwrap();
+        assert_eq!(
+            order,
+            ["c", "b", "a"].map(|n| root.join(format!("kernel/{n}.ko")))
+        );
+    }
+
+    #[test]
+    fn parameter_name() {
+        let module = LoadedModule {
+            name: "test".into(),
+            module_type: Type::Dynamic,
+            path: PathBuf::from("/sys/module/test"),
+        };
+        for name in ["", ".", "..", "../refcnt", "a/b"] {
+            for e in [
+                module.parameter_string(name).unwrap_err(),
+                module.set_parameter(name, b"1").unwrap_err(),
+            ] {
+                assert!(
+                    matches!(e.downcast_ref(), Some(ModuleError::InvalidParameter(n)) if n == name),
+                    "{name:?}: {e}"
+                );
+            }
+        }
+        assert!(module.parameter_path("debug").is_ok());
+    }
 }
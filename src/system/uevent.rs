@@ -0,0 +1,198 @@
+//! This module provides ways to listen for kernel uevents
+//!
+//! The kernel broadcasts a uevent whenever a device is added, removed, or
+//! changed, over a `NETLINK_KOBJECT_UEVENT` socket. This is what udev uses.
+//!
+//! See the [kernel docs][1] for more info
+//!
+//! [1]: https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-uevent
+use crate::util::SYSFS_PATH;
+use nix::{
+    fcntl::{fcntl, FcntlArg, OFlag},
+    sys::socket::{
+        bind, recvfrom, socket, AddressFamily, SockAddr, SockFlag, SockProtocol, SockType,
+    },
+    unistd::close,
+};
+use std::{
+    collections::HashMap,
+    io,
+    os::unix::io::{AsRawFd, RawFd},
+    path::{Path, PathBuf},
+};
+
+/// Netlink multicast group the kernel sends uevents to.
+///
+/// udev rebroadcasts processed events on group 2, which we don't want.
+const KERNEL_GROUP: u32 = 1;
+
+/// Maximum uevent message size. The kernel limit is 2048 bytes of
+/// environment, plus the header.
+const BUFFER_SIZE: usize = 8192;
+
+/// A uevent received from the kernel, see [`Monitor::recv`].
+#[derive(Debug, Clone)]
+pub struct UEventMessage {
+    /// Action, such as `add`, `remove`, `change`, `bind`, or `unbind`.
+    action: String,
+
+    /// Device path, relative to `/sys`.
+    dev_path: String,
+
+    /// Key=Value pairs, including `ACTION` and `DEVPATH`.
+    env: HashMap<String, String>,
+}
+
+// Public
+impl UEventMessage {
+    /// Action that caused this event, such as `add`, `remove`, or `change`.
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    /// Path to the device, relative to `/sys`.
+    pub fn dev_path(&self) -> &str {
+        &self.dev_path
+    }
+
+    /// Full path to the device in `/sys`.
+    ///
+    /// # Note
+    ///
+    /// The device may no longer exist, such as for `remove` events.
+    pub fn sys_path(&self) -> PathBuf {
+        Path::new(SYSFS_PATH).join(self.dev_path.trim_start_matches('/'))
+    }
+
+    /// Subsystem of the device, such as `block` or `module`, if any.
+    pub fn subsystem(&self) -> Option<&str> {
+        self.env.get("SUBSYSTEM").map(|s| s.as_str())
+    }
+
+    /// All Key=Value pairs in the event.
+    pub fn env(&self) -> &HashMap<String, String> {
+        &self.env
+    }
+}
+
+// Private
+impl UEventMessage {
+    /// Parse a kernel uevent message.
+    ///
+    /// These are formatted as `ACTION@DEVPATH`, followed by `KEY=VALUE`
+    /// pairs, all null separated.
+    fn parse(data: &[u8]) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Invalid uevent message");
+        let data = std::str::from_utf8(data).map_err(|_| invalid())?;
+        let mut i = data.split_terminator('\0');
+        let (action, dev_path) = i
+            .next()
+            .and_then(|h| h.split_once('@'))
+            .ok_or_else(invalid)?;
+        let env = i
+            .filter_map(|kv| kv.split_once('='))
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        Ok(Self {
+            action: action.into(),
+            dev_path: dev_path.into(),
+            env,
+        })
+    }
+}
+
+/// Listens for kernel uevents.
+///
+/// # Examples
+///
+/// Print every block device event
+///
+/// ```rust,no_run
+/// # use linapi::system::uevent::Monitor;
+/// let monitor = Monitor::new().unwrap();
+/// loop {
+///     let event = monitor.recv().unwrap();
+///     if event.subsystem() == Some("block") {
+///         println!("{} {}", event.action(), event.dev_path());
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Monitor {
+    fd: RawFd,
+}
+
+// Public
+impl Monitor {
+    /// Open a new uevent monitor.
+    ///
+    /// # Errors
+    ///
+    /// - If the netlink socket couldn't be created or bound
+    pub fn new() -> io::Result<Self> {
+        let fd = socket(
+            AddressFamily::Netlink,
+            SockType::Datagram,
+            SockFlag::SOCK_CLOEXEC,
+            SockProtocol::NetlinkKObjectUEvent,
+        )?;
+        // Construct first so `fd` is closed on error.
+        let s = Self { fd };
+        bind(s.fd, &SockAddr::new_netlink(0, KERNEL_GROUP))?;
+        Ok(s)
+    }
+
+    /// Receive the next uevent.
+    ///
+    /// This blocks until an event is available, unless
+    /// [`Monitor::set_nonblocking`] was used.
+    ///
+    /// Messages not sent by the kernel are ignored.
+    ///
+    /// # Errors
+    ///
+    /// - [`io::ErrorKind::WouldBlock`] if non-blocking and no event is
+    ///   available
+    /// - [`io::ErrorKind::InvalidData`] if the message was invalid
+    /// - If I/O does
+    pub fn recv(&self) -> io::Result<UEventMessage> {
+        let mut buf = vec![0; BUFFER_SIZE];
+        loop {
+            let (len, addr) = recvfrom(self.fd, &mut buf)?;
+            // Only trust messages from the kernel, which has a pid of 0.
+            match addr {
+                Some(SockAddr::Netlink(addr)) if addr.pid() == 0 => (),
+                _ => continue,
+            }
+            return UEventMessage::parse(&buf[..len]);
+        }
+    }
+
+    /// Set whether [`Monitor::recv`] blocks.
+    ///
+    /// # Errors
+    ///
+    /// - If I/O does
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        let flags = OFlag::from_bits_truncate(fcntl(self.fd, FcntlArg::F_GETFL)?);
+        let flags = if nonblocking {
+            flags | OFlag::O_NONBLOCK
+        } else {
+            flags - OFlag::O_NONBLOCK
+        };
+        fcntl(self.fd, FcntlArg::F_SETFL(flags))?;
+        Ok(())
+    }
+}
+
+impl AsRawFd for Monitor {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        let _ = close(self.fd);
+    }
+}
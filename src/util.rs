@@ -1,6 +1,9 @@
 //! Utility functions
 use crate::system::UEventAction;
-use std::{collections::HashMap, fs, io::prelude::*, path::Path};
+use std::{
+    collections::HashMap, ffi::CStr, fs, io, io::prelude::*, mem::MaybeUninit, path::Path,
+    str::FromStr, sync::OnceLock,
+};
 
 /// Technically Linux requires sysfs to be at `/sys`, calling it a system
 /// configuration error otherwise.
@@ -9,27 +12,92 @@ use std::{collections::HashMap, fs, io::prelude::*, path::Path};
 /// changes, including of `/sys`, so do this to allow easily changing it.
 pub const SYSFS_PATH: &str = "/sys";
 
+/// procfs location. Same reasons as [`SYSFS_PATH`].
+pub const PROC_PATH: &str = "/proc";
+
 /// Kernel Module location. Same reasons as [`SYSFS_PATH`].
 pub const MODULE_PATH: &str = "/lib/modules";
 
+/// Firmware location. Same reasons as [`SYSFS_PATH`].
+pub const FIRMWARE_PATH: &str = "/lib/firmware";
+
 /// Device file location. Same reasons as [`SYSFS_PATH`].
 pub const DEV_PATH: &str = "/dev";
 
+/// Release of the running kernel, such as `5.10.0-1-amd64`, from `uname(2)`.
+///
+/// This is cached, as it can't change while the system is running.
+///
+/// Invalid UTF-8 is replaced, see [`String::from_utf8_lossy`].
+pub fn kernel_release() -> &'static str {
+    static RELEASE: OnceLock<String> = OnceLock::new();
+    RELEASE.get_or_init(|| {
+        let mut uts = MaybeUninit::<libc::utsname>::uninit();
+        // Safety: `uname` only fails if `uts` is an invalid pointer, and
+        // fills in nul terminated strings.
+        unsafe {
+            libc::uname(uts.as_mut_ptr());
+            CStr::from_ptr(uts.assume_init_ref().release.as_ptr())
+                .to_string_lossy()
+                .into_owned()
+        }
+    })
+}
+
+/// Read the sysfs `attribute` at `base`, trimmed.
+pub fn read_attr<P: AsRef<Path>>(base: P, attribute: &str) -> io::Result<String> {
+    fs::read_to_string(base.as_ref().join(attribute)).map(|s| s.trim().to_owned())
+}
+
+/// Like [`read_attr`], but returns [`None`] if `attribute` doesn't exist.
+pub fn read_attr_optional<P: AsRef<Path>>(base: P, attribute: &str) -> io::Result<Option<String>> {
+    match read_attr(base, attribute) {
+        Ok(s) => Ok(Some(s)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Like [`read_attr`], but parses the attribute.
+///
+/// # Errors
+///
+/// - [`io::ErrorKind::InvalidData`] if the attribute couldn't be parsed
+/// - If I/O does
+pub fn read_attr_parsed<T: FromStr, P: AsRef<Path>>(base: P, attribute: &str) -> io::Result<T> {
+    read_attr(base, attribute)?.parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid sysfs attribute {}", attribute),
+        )
+    })
+}
+
+/// Map errors from [`read_attr_parsed`] into a module's `Error` type, using
+/// `invalid` if the attribute couldn't be parsed.
+pub fn attr_error<E: From<io::Error>>(e: io::Error, invalid: E) -> E {
+    if e.kind() == io::ErrorKind::InvalidData {
+        invalid
+    } else {
+        e.into()
+    }
+}
+
 /// Read a uevent file
 ///
+/// Lines without a `=` are skipped.
+///
 /// # Arguments
 ///
 /// - `path`, path to the uevent file.
-pub fn read_uevent(path: &Path) -> HashMap<String, String> {
+pub fn read_uevent(path: &Path) -> io::Result<HashMap<String, String>> {
     let mut map = HashMap::new();
-    for line in fs::read_to_string(path).unwrap().split_terminator('\n') {
-        let line: &str = line;
-        let mut i = line.split('=');
-        let key = i.next().unwrap().into();
-        let val = i.next().unwrap().into();
-        map.insert(key, val);
+    for line in fs::read_to_string(path)?.split_terminator('\n') {
+        if let Some((key, val)) = line.split_once('=') {
+            map.insert(key.into(), val.into());
+        }
     }
-    map
+    Ok(map)
 }
 
 /// Write a uevent file
@@ -42,7 +110,7 @@ pub fn write_uevent(
     action: UEventAction,
     uuid: Option<String>,
     args: HashMap<String, String>,
-) {
+) -> io::Result<()> {
     let mut data = String::new();
     match action {
         UEventAction::Add => data.push_str("add"),
@@ -61,6 +129,6 @@ pub fn write_uevent(
         data.push(' ');
     }
     //
-    let mut f = fs::OpenOptions::new().write(true).open(path).unwrap();
-    f.write_all(data.trim().as_bytes()).unwrap();
+    let mut f = fs::OpenOptions::new().write(true).open(path)?;
+    f.write_all(data.trim().as_bytes())
 }